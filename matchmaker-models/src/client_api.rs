@@ -1,15 +1,58 @@
+use crate::server_api::MatchResult;
 use serde::{Deserialize, Serialize};
 
+/// Most players a lobby can have.
+pub const MAX_PLAYERS: u8 = 8;
+
+/// Sent to register an account or to log in.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct User {
+    pub id: u64,
+    pub username: String,
+}
+
+/// Proves who is logged in. The token is sent as `Authorization: Bearer <token>`
+/// to create and join lobbies.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct Session {
+    pub token: String,
+    pub user: User,
+    /// Seconds until the token expires and the user has to log in again.
+    pub expires_in: u64,
+}
+
+/// The host of the lobby is the logged in user.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub struct LobbyCreation {
     pub name: String,
-    pub host: String,
+    pub max_players: u8,
+    /// Players have to know it to join, if set.
+    pub password: Option<String>,
+    /// Hidden from the lobby list, only joinable with the invite code.
+    pub unlisted: bool,
+}
+
+/// The lobby that was created, with the code to invite players if it is unlisted.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct CreatedLobby {
+    pub lobby: Lobby,
+    pub invite_code: Option<String>,
+    /// Only known to the host, which needs it to register the server and report on the lobby.
+    pub host_token: String,
 }
 
+/// The player joins as the logged in user.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
-pub struct PlayerCountSettings {
-    pub count: u8,
-    pub secret: String,
+pub struct JoinRequest {
+    pub password: Option<String>,
+    /// Needed to join unlisted lobbies.
+    pub invite_code: Option<String>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
@@ -23,4 +66,15 @@ pub struct Lobby {
     pub name: String,
     pub playing: bool,
     pub player_count: u8,
+    pub max_players: u8,
+    pub password_protected: bool,
+}
+
+/// A match that was played to the end in one of the lobbies.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct FinishedMatch {
+    pub lobby: String,
+    /// Seconds since the unix epoch.
+    pub finished_at: u64,
+    pub result: MatchResult,
 }
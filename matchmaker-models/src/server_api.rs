@@ -1,11 +1,72 @@
-use renet::NETCODE_USER_DATA_BYTES;
+use renet::{NETCODE_KEY_BYTES, NETCODE_USER_DATA_BYTES};
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
+use std::net::SocketAddr;
 
 pub const PROTOCOL_ID: u64 = 7;
 
+/// Sent by a host to announce where the server of its lobby can be reached.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct ServerRegistration {
+    pub address: SocketAddr,
+    /// The token the host got when it created the lobby.
+    pub host_token: String,
+}
+
+/// Sent by a host regularly, so the matchmaker knows its lobby is still alive.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct ServerHeartbeat {
+    pub host_token: String,
+}
+
+/// Sent by a host whenever something happens in its lobby. Every report also counts as a heartbeat.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct LobbyReport {
+    pub event: LobbyEvent,
+    pub host_token: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub enum LobbyEvent {
+    PlayerJoined {
+        username: String,
+    },
+    PlayerLeft {
+        username: String,
+    },
+    /// The host changed how many players may join the lobby.
+    CapacityChanged {
+        max_players: u8,
+    },
+    MatchStarted,
+    MatchFinished(MatchResult),
+}
+
+/// How a match ended, with the players in the order of their seats.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct MatchResult {
+    pub winner: Option<String>,
+    pub players: Vec<PlayerResult>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct PlayerResult {
+    pub username: String,
+    /// Pigs the player had left when the match ended.
+    pub pigs: u32,
+}
+
+/// What a registered host needs to accept the connect tokens of the matchmaker.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct ServerCredentials {
+    /// Only valid for the connect tokens of this lobby, every lobby gets its own key.
+    pub private_key: [u8; NETCODE_KEY_BYTES],
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub struct ConnectionData {
+    /// Id of the account the matchmaker authenticated, which servers can trust.
+    pub user_id: u64,
     pub username: String,
     pub lobby: String,
 }
@@ -14,20 +75,22 @@ const DATA_PARTS: usize = 2;
 type DataLen = u8;
 /// Guaranteed to actually use less, since we don't account for the length of the header in the header itself
 const HEADER_PART_BYTES: usize = NETCODE_USER_DATA_BYTES / (DataLen::MAX as usize + 1);
-/// Format: <length of username> <length of lobby name> <username> <lobby name>
-const HEADER_BYTES: usize = HEADER_PART_BYTES * DATA_PARTS;
+const USER_ID_BYTES: usize = std::mem::size_of::<u64>();
+/// Format: <length of username> <length of lobby name> <user id> <username> <lobby name>
+const HEADER_BYTES: usize = HEADER_PART_BYTES * DATA_PARTS + USER_ID_BYTES;
 const MAX_DATA_PART_BYTES: usize = (NETCODE_USER_DATA_BYTES - HEADER_BYTES) / DATA_PARTS;
 impl ConnectionData {
     pub fn is_valid_data_part(data: &str) -> bool {
         data.len() <= MAX_DATA_PART_BYTES
     }
 
-    pub fn try_new(username: &str, lobby: &str) -> Option<Self> {
+    pub fn try_new(user_id: u64, username: &str, lobby: &str) -> Option<Self> {
         if !Self::is_valid_data_part(username) || !Self::is_valid_data_part(lobby) {
             return None;
         }
 
         Self {
+            user_id,
             username: username.to_string(),
             lobby: lobby.to_string(),
         }
@@ -45,6 +108,9 @@ impl ConnectionData {
         user_data[HEADER_PART_BYTES..HEADER_PART_BYTES * 2]
             .copy_from_slice((lobby_bytes.len() as DataLen).to_le_bytes().as_slice());
 
+        user_data[HEADER_PART_BYTES * 2..HEADER_BYTES]
+            .copy_from_slice(self.user_id.to_le_bytes().as_slice());
+
         (&mut user_data[HEADER_BYTES..HEADER_BYTES + MAX_DATA_PART_BYTES])
             .write(username_bytes)
             .unwrap();
@@ -60,7 +126,12 @@ impl ConnectionData {
         let username_len =
             DataLen::from_le_bytes(user_data[0..HEADER_PART_BYTES].try_into().unwrap());
         let lobby_len = DataLen::from_le_bytes(
-            user_data[HEADER_PART_BYTES..HEADER_BYTES]
+            user_data[HEADER_PART_BYTES..HEADER_PART_BYTES * 2]
+                .try_into()
+                .unwrap(),
+        );
+        let user_id = u64::from_le_bytes(
+            user_data[HEADER_PART_BYTES * 2..HEADER_BYTES]
                 .try_into()
                 .unwrap(),
         );
@@ -71,7 +142,11 @@ impl ConnectionData {
             ..HEADER_BYTES + MAX_DATA_PART_BYTES + lobby_len as usize])
             .read_to_string(&mut lobby)
             .unwrap();
-        Self { username, lobby }
+        Self {
+            user_id,
+            username,
+            lobby,
+        }
     }
 }
 
@@ -81,14 +156,14 @@ mod test {
 
     #[test]
     fn can_be_created_from_valid_data() {
-        let data = ConnectionData::try_new("username", "lobby");
+        let data = ConnectionData::try_new(1, "username", "lobby");
         assert!(data.is_some());
     }
 
     #[test]
     fn cannot_be_created_from_invalid_data() {
         let username = std::iter::repeat("a").take(300).collect::<String>();
-        let data = ConnectionData::try_new(&username, "lobby");
+        let data = ConnectionData::try_new(1, &username, "lobby");
         assert!(data.is_none());
     }
 
@@ -115,11 +190,22 @@ mod test {
         assert_eq!(sent_data, received_data);
     }
 
+    #[test]
+    fn keeps_the_user_id_next_to_long_names() {
+        let name = std::iter::repeat("a")
+            .take(MAX_DATA_PART_BYTES)
+            .collect::<String>();
+        let sent_data = ConnectionData::try_new(u64::MAX, &name, &name).unwrap();
+        let netcode_bytes = sent_data.to_netcode_user_data();
+        let received_data = ConnectionData::from_user_data(&netcode_bytes);
+        assert_eq!(sent_data, received_data);
+    }
+
     fn get_valid_connection_data() -> ConnectionData {
-        ConnectionData::try_new("username", "lobby").unwrap()
+        ConnectionData::try_new(42, "username", "lobby").unwrap()
     }
 
     fn get_valid_weird_connection_data() -> ConnectionData {
-        ConnectionData::try_new(" 😊🐬💕😘👌  \n", "\t😊🐬💕aa😘👌  \n").unwrap()
+        ConnectionData::try_new(7, " 😊🐬💕😘👌  \n", "\t😊🐬💕aa😘👌  \n").unwrap()
     }
 }
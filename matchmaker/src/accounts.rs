@@ -0,0 +1,184 @@
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::json::Json;
+use rocket::serde::Deserialize;
+use rocket::{Route, State};
+use rocket_db_pools::deadpool_redis::redis::{self, AsyncCommands};
+use rocket_db_pools::Connection;
+
+use matchmaker_models::client_api::{Credentials, Session, User};
+use matchmaker_models::server_api::ConnectionData;
+use serde_redis::RedisDeserialize;
+
+use crate::client_api::Lobbies;
+use crate::config::MatchmakerConfig;
+use crate::secrets;
+
+/// Counter the ids of new accounts are taken from.
+const USER_ID_COUNTER: &str = "matchmaker/next_user_id";
+const MAX_USERNAME_CHARS: usize = 32;
+
+/// Creates an account only if the username is not taken yet. Returns the id of the account, or 0.
+/// KEYS: account hash, user id counter. ARGV: username, password hash.
+const REGISTER_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    return 0
+end
+local id = redis.call('INCR', KEYS[2])
+redis.call('HSET', KEYS[1], 'id', id, 'username', ARGV[1], 'password_hash', ARGV[2])
+return id
+";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Account {
+    id: u64,
+    username: String,
+    password_hash: String,
+}
+
+/// A user that sent a valid session token along with the request.
+pub struct Authenticated(pub User);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Authenticated {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "));
+        let token = match token {
+            Some(token) => token.to_string(),
+            None => return Outcome::Failure((Status::Unauthorized, ())),
+        };
+        let mut db = match request.guard::<Connection<Lobbies>>().await {
+            Outcome::Success(db) => db,
+            Outcome::Failure((status, _)) => return Outcome::Failure((status, ())),
+            Outcome::Forward(forward) => return Outcome::Forward(forward),
+        };
+        match query_session(&token, &mut db).await {
+            Some(user) => Outcome::Success(Authenticated(user)),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// Creates an account and logs it in right away.
+#[put("/accounts", format = "json", data = "<credentials>")]
+async fn register(
+    credentials: Json<Credentials>,
+    config: &State<MatchmakerConfig>,
+    mut db: Connection<Lobbies>,
+) -> Result<Json<Session>, Status> {
+    let credentials = credentials.0;
+    if !is_valid_username(&credentials.username) || credentials.password.is_empty() {
+        return Err(Status::BadRequest);
+    }
+    let id: u64 = redis::cmd("EVAL")
+        .arg(REGISTER_SCRIPT)
+        .arg(2)
+        .arg(get_account_name(&credentials.username))
+        .arg(USER_ID_COUNTER)
+        .arg(&credentials.username)
+        .arg(secrets::hash_password(&credentials.password).await)
+        .query_async(&mut **db)
+        .await
+        .unwrap();
+    if id == 0 {
+        return Err(Status::Conflict);
+    }
+    let user = User {
+        id,
+        username: credentials.username,
+    };
+    Ok(Json(start_session(user, config, &mut db).await))
+}
+
+#[put("/sessions", format = "json", data = "<credentials>")]
+async fn log_in(
+    credentials: Json<Credentials>,
+    config: &State<MatchmakerConfig>,
+    mut db: Connection<Lobbies>,
+) -> Result<Json<Session>, Status> {
+    let credentials = credentials.0;
+    let account_value: redis::Value = db
+        .hgetall(get_account_name(&credentials.username))
+        .await
+        .unwrap();
+    let account: Account = match account_value.deserialize() {
+        Ok(account) => account,
+        Err(_) => return Err(Status::Unauthorized),
+    };
+    if !secrets::verify_password(&credentials.password, &account.password_hash).await {
+        return Err(Status::Unauthorized);
+    }
+    let user = User {
+        id: account.id,
+        username: account.username,
+    };
+    Ok(Json(start_session(user, config, &mut db).await))
+}
+
+/// The user the session token in the `Authorization` header belongs to,
+/// so servers can check the tokens they are given. Fails with 401 if the session does not exist.
+#[get("/sessions/current")]
+async fn get_session(Authenticated(user): Authenticated) -> Json<User> {
+    Json(user)
+}
+
+async fn start_session(
+    user: User,
+    config: &MatchmakerConfig,
+    db: &mut Connection<Lobbies>,
+) -> Session {
+    let token = secrets::generate_session_token();
+    let session_name = get_session_name(&token);
+    let _: () = redis::pipe()
+        .atomic()
+        .hset_multiple(
+            &session_name,
+            &[
+                ("id", user.id.to_string()),
+                ("username", user.username.clone()),
+            ],
+        )
+        .ignore()
+        .expire(&session_name, config.session_ttl as usize)
+        .ignore()
+        .query_async(&mut **db)
+        .await
+        .unwrap();
+    Session {
+        token,
+        user,
+        expires_in: config.session_ttl,
+    }
+}
+
+async fn query_session(token: &str, db: &mut Connection<Lobbies>) -> Option<User> {
+    let user_value: redis::Value = db.hgetall(get_session_name(token)).await.unwrap();
+    user_value.deserialize().ok()
+}
+
+/// Usernames end up in the connect tokens, so they have to fit in there.
+fn is_valid_username(username: &str) -> bool {
+    !username.is_empty()
+        && username.trim() == username
+        && username.chars().count() <= MAX_USERNAME_CHARS
+        && ConnectionData::is_valid_data_part(username)
+}
+
+/// Usernames are unique regardless of their case.
+fn get_account_name(username: &str) -> String {
+    format!("matchmaker/account:{}", username.to_lowercase())
+}
+
+fn get_session_name(token: &str) -> String {
+    format!("matchmaker/session:{}", token)
+}
+
+pub(crate) fn get_routes() -> Vec<Route> {
+    routes![register, log_in, get_session]
+}
@@ -1,123 +1,524 @@
+use renet::NETCODE_KEY_BYTES;
 use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::Route;
+use rocket::serde::json::{self, Json};
+use rocket::serde::Deserialize;
+use rocket::{Route, State};
 use rocket_db_pools::deadpool_redis::redis::AsyncCommands;
 use rocket_db_pools::deadpool_redis::{self, redis};
 use rocket_db_pools::{Connection, Database};
 
 use matchmaker_models::client_api::*;
+use matchmaker_models::server_api::{
+    ConnectionData, LobbyEvent, LobbyReport, MatchResult, ServerCredentials, ServerHeartbeat,
+    ServerRegistration,
+};
 use serde_redis::RedisDeserialize;
+use std::net::SocketAddr;
+use std::time::SystemTime;
 
+use crate::accounts::Authenticated;
+use crate::config::MatchmakerConfig;
+use crate::secrets;
 use crate::server_connection::create_client_connection_data;
 
+/// Set of the hash names of every lobby.
+pub(crate) const LOBBY_SET: &str = "matchmaker/lobbies";
+
+/// List of the finished matches as JSON, latest first.
+const MATCH_LIST: &str = "matchmaker/matches";
+/// Older matches are dropped from the list.
+const RECORDED_MATCHES: isize = 1000;
+const LISTED_MATCHES: isize = 50;
+
+/// Creates the hash of a lobby only if it does not exist yet, with its expiry.
+/// Listed lobbies join the lobby set, unlisted ones claim their invite code instead.
+/// Players and reservations of an earlier lobby with the same name are forgotten.
+/// Returns 0 if the name is taken and -1 if the invite code is.
+/// KEYS: lobby hash, lobby set, lobby players, lobby reservations, invite.
+/// ARGV: name, host, maximum player count, seconds until the lobby expires,
+/// password hash or empty, `true` if unlisted, invite code or empty, host token.
+const CREATE_LOBBY_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    return 0
+end
+local unlisted = ARGV[6] == 'true'
+if unlisted and not redis.call('SET', KEYS[5], ARGV[1], 'NX', 'EX', ARGV[4]) then
+    return -1
+end
+redis.call('DEL', KEYS[3], KEYS[4])
+redis.call('HSET', KEYS[1], 'name', ARGV[1], 'host', ARGV[2], 'playing', 'false',
+    'player_count', '0', 'max_players', ARGV[3], 'password_protected', tostring(ARGV[5] ~= ''),
+    'password_hash', ARGV[5], 'unlisted', ARGV[6], 'invite_code', ARGV[7], 'host_token', ARGV[8])
+redis.call('EXPIRE', KEYS[1], ARGV[4])
+if not unlisted then
+    redis.call('SADD', KEYS[2], KEYS[1])
+end
+return 1
+";
+
+/// Adds a player to or removes a player from a lobby that still exists, and updates its player count.
+/// A player that is added no longer needs the seat reserved when joining.
+/// KEYS: lobby hash, lobby players, lobby reservations.
+/// ARGV: `SADD` or `SREM`, username, seconds until the lobby expires.
+const UPDATE_PLAYERS_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 0
+end
+redis.call(ARGV[1], KEYS[2], ARGV[2])
+redis.call('ZREM', KEYS[3], ARGV[2])
+redis.call('HSET', KEYS[1], 'player_count', redis.call('SCARD', KEYS[2]))
+redis.call('EXPIRE', KEYS[2], ARGV[3])
+return 1
+";
+
+/// Reserves a seat in a lobby until the player connects to its server or the reservation expires.
+/// While a match is running, only its players may join again.
+/// KEYS: lobby hash, lobby players, lobby reservations.
+/// ARGV: username, current unix time, unix time the reservation expires, seconds until the lobby expires.
+const RESERVE_SEAT_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 'missing'
+end
+redis.call('ZREMRANGEBYSCORE', KEYS[3], '-inf', ARGV[2])
+local seated = redis.call('SISMEMBER', KEYS[2], ARGV[1]) == 1
+if redis.call('HGET', KEYS[1], 'playing') == 'true' then
+    if seated then
+        return 'reserved'
+    end
+    return 'locked'
+end
+if seated or redis.call('ZSCORE', KEYS[3], ARGV[1]) then
+    return 'taken'
+end
+local max_players = tonumber(redis.call('HGET', KEYS[1], 'max_players'))
+if redis.call('SCARD', KEYS[2]) + redis.call('ZCARD', KEYS[3]) >= max_players then
+    return 'locked'
+end
+redis.call('ZADD', KEYS[3], ARGV[3], ARGV[1])
+redis.call('EXPIRE', KEYS[3], ARGV[4])
+return 'reserved'
+";
+
+/// Attempts to find an invite code that is not taken yet.
+const INVITE_CODE_ATTEMPTS: usize = 5;
+
 #[derive(Database)]
 #[database("lobbies")]
 pub struct Lobbies(deadpool_redis::Pool);
 
+/// Who may join a lobby. Only stored in the hash of the lobby, never handed out.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct LobbyAccess {
+    unlisted: bool,
+    /// Empty for listed lobbies.
+    invite_code: String,
+    /// Empty for lobbies without a password.
+    password_hash: String,
+}
+
 #[get("/lobbies")]
 async fn list_lobbies(mut db: Connection<Lobbies>) -> Json<Vec<Lobby>> {
-    let lobby_names: Vec<String> = db.smembers("matchmaker/lobbies").await.unwrap();
+    let lobby_names: Vec<String> = db.smembers(LOBBY_SET).await.unwrap();
 
     let mut lobbies = Vec::new();
     for lobby_name in lobby_names {
         let lobby_value: redis::Value = db.hgetall(&lobby_name).await.unwrap();
-        let lobby: Lobby = lobby_value.deserialize().unwrap();
-        lobbies.push(lobby);
+        // Expired lobbies stay in the set until the reaper comes along
+        if let Ok(lobby) = lobby_value.deserialize() {
+            lobbies.push(lobby);
+        }
     }
     Json(lobbies)
 }
 
+/// Unlisted lobbies are only found with their invite code.
 #[get("/lobbies/<lobby>")]
 async fn get_lobby(lobby: String, mut db: Connection<Lobbies>) -> Json<Option<Lobby>> {
-    let lobby = query_lobby(&lobby, &mut db).await;
-    Json(lobby)
+    match query_access(&lobby, &mut db).await {
+        Some(access) if !access.unlisted => Json(query_lobby(&lobby, &mut db).await),
+        _ => Json(None),
+    }
+}
+
+/// The lobby an invite code leads to.
+#[get("/invites/<code>")]
+async fn find_invite(code: String, mut db: Connection<Lobbies>) -> Json<Option<Lobby>> {
+    let lobby: Option<String> = db.get(get_invite_name(&code)).await.unwrap();
+    match lobby {
+        Some(lobby) => Json(query_lobby(&lobby, &mut db).await),
+        None => Json(None),
+    }
 }
 
 async fn query_lobby(lobby: &str, db: &mut Connection<Lobbies>) -> Option<Lobby> {
-    let lobby_value: redis::Value = db.hgetall(&lobby).await.unwrap();
+    let lobby_value: redis::Value = db.hgetall(get_hash_name(lobby)).await.unwrap();
     lobby_value.deserialize().ok()
 }
 
+async fn query_access(lobby: &str, db: &mut Connection<Lobbies>) -> Option<LobbyAccess> {
+    let lobby_value: redis::Value = db.hgetall(get_hash_name(lobby)).await.unwrap();
+    lobby_value.deserialize().ok()
+}
+
+/// The address the host of the lobby registered and the key of its connect tokens, if any.
+async fn query_server(
+    lobby: &str,
+    db: &mut Connection<Lobbies>,
+) -> Option<(SocketAddr, [u8; NETCODE_KEY_BYTES])> {
+    let (address, key): (Option<String>, Option<String>) = db
+        .hget(get_hash_name(lobby), &["server_address", "server_key"])
+        .await
+        .unwrap();
+    let address = address?.parse().ok()?;
+    let key = hex::decode(key?).ok()?.try_into().ok()?;
+    Some((address, key))
+}
+
+/// Creates a lobby hosted by the logged in user.
+/// Fails with 400 if the name or the capacity is not allowed.
 #[put("/lobbies", format = "json", data = "<lobby>")]
 async fn create_lobby(
+    Authenticated(host): Authenticated,
     lobby: Json<LobbyCreation>,
+    config: &State<MatchmakerConfig>,
     mut db: Connection<Lobbies>,
-) -> Result<Json<LobbyResponse>, Status> {
+) -> Result<Json<CreatedLobby>, Status> {
     let lobby = lobby.0;
-    if query_lobby(&lobby.name, &mut db).await.is_some() {
-        return Err(Status::Conflict);
+    if !is_valid_lobby_name(&lobby.name) || !is_valid_capacity(lobby.max_players) {
+        return Err(Status::BadRequest);
     }
+    let password_hash = match lobby.password.as_deref() {
+        Some(password) if !password.is_empty() => secrets::hash_password(password).await,
+        _ => String::new(),
+    };
+    let host_token = secrets::generate_host_token();
+    for _ in 0..INVITE_CODE_ATTEMPTS {
+        let invite_code = match lobby.unlisted {
+            true => secrets::generate_invite_code(),
+            false => String::new(),
+        };
+        let created: i64 = redis::cmd("EVAL")
+            .arg(CREATE_LOBBY_SCRIPT)
+            .arg(5)
+            .arg(get_hash_name(&lobby.name))
+            .arg(LOBBY_SET)
+            .arg(get_players_name(&lobby.name))
+            .arg(get_reservations_name(&lobby.name))
+            .arg(get_invite_name(&invite_code))
+            .arg(&lobby.name)
+            .arg(&host.username)
+            .arg(lobby.max_players)
+            .arg(config.lobby_ttl)
+            .arg(&password_hash)
+            .arg(lobby.unlisted.to_string())
+            .arg(&invite_code)
+            .arg(&host_token)
+            .query_async(&mut **db)
+            .await
+            .unwrap();
+        match created {
+            0 => return Err(Status::Conflict),
+            // The invite code is taken, try another one
+            -1 => continue,
+            _ => {}
+        }
+        let created = query_lobby(&lobby.name, &mut db)
+            .await
+            .map(|lobby| CreatedLobby {
+                lobby,
+                invite_code: lobby_invite_code(invite_code),
+                host_token,
+            });
+        return created.map(Json).ok_or(Status::InternalServerError);
+    }
+    Err(Status::InternalServerError)
+}
 
-    let hash_name = get_hash_name(&lobby.name);
-
+/// Lets the host of a lobby announce where its server can be reached.
+/// In return it gets the key that the connect tokens for the lobby are encrypted with.
+/// Every lobby gets its own key, so hosts cannot create tokens for the servers of others.
+#[put("/lobbies/<lobby>/server", format = "json", data = "<registration>")]
+async fn register_server(
+    lobby: String,
+    registration: Json<ServerRegistration>,
+    config: &State<MatchmakerConfig>,
+    mut db: Connection<Lobbies>,
+) -> Result<Json<ServerCredentials>, Status> {
+    let registration = registration.0;
+    authorize_host(&lobby, &registration.host_token, &mut db).await?;
+    let private_key = secrets::generate_server_key();
     let _: () = db
         .hset_multiple(
-            &hash_name,
+            get_hash_name(&lobby),
             &[
-                ("name", lobby.name.clone()),
-                ("playing", false.to_string()),
-                ("player_count", 0.to_string()),
+                ("server_address", registration.address.to_string()),
+                ("server_key", hex::encode(private_key)),
             ],
         )
         .await
         .unwrap();
+    refresh_lobby(&lobby, config, &mut db).await;
+    Ok(Json(ServerCredentials { private_key }))
+}
 
-    let _: () = db.sadd("matchmaker/lobbies", &hash_name).await.unwrap();
-    let response = create_client_connection_data(&lobby.name, &lobby.host);
-    Ok(response.into())
+/// Keeps the lobby alive. Hosts have to call this more often than the lobbies expire.
+#[put("/lobbies/<lobby>/heartbeat", format = "json", data = "<heartbeat>")]
+async fn heartbeat(
+    lobby: String,
+    heartbeat: Json<ServerHeartbeat>,
+    config: &State<MatchmakerConfig>,
+    mut db: Connection<Lobbies>,
+) -> Result<Json<()>, Status> {
+    authorize_host(&lobby, &heartbeat.0.host_token, &mut db).await?;
+    if !refresh_lobby(&lobby, config, &mut db).await {
+        return Err(Status::NotFound);
+    }
+    Ok(Json(()))
 }
 
-#[put("/lobbies/<lobby>", format = "json", data = "<username>")]
-async fn join_lobby(lobby: String, username: Json<String>) -> Json<LobbyResponse> {
-    // Setting the player count is the job of the server now.
-    create_client_connection_data(&lobby, &username.0).into()
+/// Reserves a seat in the lobby for the logged in user and hands out a connect token for its server.
+/// Fails with 401 without a valid session, with 404 if the lobby is unlisted and the invite code is missing,
+/// with 403 if the password is wrong, with 409 if the user is already in the lobby,
+/// and with 423 if the lobby is full or its match has started without the player.
+#[put("/lobbies/<lobby>", format = "json", data = "<request>")]
+async fn join_lobby(
+    lobby: String,
+    Authenticated(user): Authenticated,
+    request: Json<JoinRequest>,
+    config: &State<MatchmakerConfig>,
+    mut db: Connection<Lobbies>,
+) -> Result<Json<LobbyResponse>, Status> {
+    let JoinRequest {
+        password,
+        invite_code,
+    } = request.0;
+    let access = query_access(&lobby, &mut db)
+        .await
+        .ok_or(Status::NotFound)?;
+    let invited = invite_code
+        .map(|code| code.to_uppercase() == access.invite_code)
+        .unwrap_or(false);
+    if access.unlisted && !invited {
+        return Err(Status::NotFound);
+    }
+    let password = password.unwrap_or_default();
+    if !access.password_hash.is_empty()
+        && !secrets::verify_password(&password, &access.password_hash).await
+    {
+        return Err(Status::Forbidden);
+    }
+    let (server_address, server_key) = query_server(&lobby, &mut db)
+        .await
+        .ok_or(Status::ServiceUnavailable)?;
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let reservation: String = redis::cmd("EVAL")
+        .arg(RESERVE_SEAT_SCRIPT)
+        .arg(3)
+        .arg(get_hash_name(&lobby))
+        .arg(get_players_name(&lobby))
+        .arg(get_reservations_name(&lobby))
+        .arg(&user.username)
+        .arg(now)
+        .arg(now + config.reservation_expiry)
+        .arg(config.lobby_ttl)
+        .query_async(&mut **db)
+        .await
+        .unwrap();
+    match reservation.as_str() {
+        "reserved" => {}
+        "missing" => return Err(Status::NotFound),
+        "taken" => return Err(Status::Conflict),
+        _ => return Err(Status::Locked),
+    }
+    // The reservation is confirmed once the server reports that the player connected
+    let response =
+        create_client_connection_data(config, server_address, &server_key, &lobby, &user);
+    Ok(response.into())
 }
 
-#[put(
-    "/lobbies/<lobby>/playercount",
-    format = "json",
-    data = "<player_count_settings>"
-)]
-async fn set_player_count(
+/// Lets the host of a lobby report what happens in it, so the lobby list reflects reality.
+/// Fails with 400 if the host sets a capacity that is not allowed.
+#[put("/lobbies/<lobby>/events", format = "json", data = "<report>")]
+async fn report_event(
     lobby: String,
-    player_count_settings: Json<PlayerCountSettings>,
+    report: Json<LobbyReport>,
+    config: &State<MatchmakerConfig>,
     mut db: Connection<Lobbies>,
-) -> Status {
-    let player_count_settings = player_count_settings.0;
-    if player_count_settings.secret != "secret" {
-        return Status::Unauthorized;
+) -> Result<Json<()>, Status> {
+    let report = report.0;
+    authorize_host(&lobby, &report.host_token, &mut db).await?;
+    if !refresh_lobby(&lobby, config, &mut db).await {
+        return Err(Status::NotFound);
+    }
+    match report.event {
+        LobbyEvent::PlayerJoined { username } => {
+            update_players("SADD", &lobby, &username, config, &mut db).await
+        }
+        LobbyEvent::PlayerLeft { username } => {
+            update_players("SREM", &lobby, &username, config, &mut db).await
+        }
+        LobbyEvent::CapacityChanged { max_players } => {
+            if !is_valid_capacity(max_players) {
+                return Err(Status::BadRequest);
+            }
+            let _: () = db
+                .hset(get_hash_name(&lobby), "max_players", max_players)
+                .await
+                .unwrap();
+        }
+        LobbyEvent::MatchStarted => set_playing(&lobby, true, &mut db).await,
+        LobbyEvent::MatchFinished(result) => {
+            set_playing(&lobby, false, &mut db).await;
+            record_match(&lobby, result, &mut db).await;
+        }
     }
-    let lobby = format!("matchmaker/lobby:{}", lobby);
+    Ok(Json(()))
+}
+
+/// The latest matches that were played to the end, latest first.
+#[get("/matches")]
+async fn list_matches(mut db: Connection<Lobbies>) -> Json<Vec<FinishedMatch>> {
+    let matches: Vec<String> = db.lrange(MATCH_LIST, 0, LISTED_MATCHES - 1).await.unwrap();
+    let matches = matches
+        .iter()
+        .filter_map(|record| json::from_str(record).ok())
+        .collect();
+    Json(matches)
+}
+
+/// Adds or removes a player with `command` and updates the player count of the lobby.
+async fn update_players(
+    command: &str,
+    lobby: &str,
+    username: &str,
+    config: &MatchmakerConfig,
+    db: &mut Connection<Lobbies>,
+) {
+    let _: bool = redis::cmd("EVAL")
+        .arg(UPDATE_PLAYERS_SCRIPT)
+        .arg(3)
+        .arg(get_hash_name(lobby))
+        .arg(get_players_name(lobby))
+        .arg(get_reservations_name(lobby))
+        .arg(command)
+        .arg(username)
+        .arg(config.lobby_ttl)
+        .query_async(&mut **db)
+        .await
+        .unwrap();
+}
+
+async fn set_playing(lobby: &str, playing: bool, db: &mut Connection<Lobbies>) {
     let _: () = db
-        .hset(
-            &lobby,
-            "player_count",
-            player_count_settings.count.to_string(),
-        )
+        .hset(get_hash_name(lobby), "playing", playing.to_string())
         .await
         .unwrap();
-    if player_count_settings.count == 0 {
-        delete_lobby(&lobby, &mut db).await;
+}
+
+async fn record_match(lobby: &str, result: MatchResult, db: &mut Connection<Lobbies>) {
+    let finished_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let record = FinishedMatch {
+        lobby: lobby.to_string(),
+        finished_at,
+        result,
+    };
+    let record = json::to_string(&record).unwrap();
+    let _: () = db.lpush(MATCH_LIST, record).await.unwrap();
+    let _: () = db.ltrim(MATCH_LIST, 0, RECORDED_MATCHES - 1).await.unwrap();
+}
+
+/// Only the host of a lobby knows its token.
+/// Fails with 404 if the lobby does not exist and with 401 if the token is wrong.
+async fn authorize_host(
+    lobby: &str,
+    host_token: &str,
+    db: &mut Connection<Lobbies>,
+) -> Result<(), Status> {
+    let expected: Option<String> = db.hget(get_hash_name(lobby), "host_token").await.unwrap();
+    match expected {
+        Some(expected) if secrets::constant_time_eq(expected.as_bytes(), host_token.as_bytes()) => {
+            Ok(())
+        }
+        Some(_) => Err(Status::Unauthorized),
+        None => Err(Status::NotFound),
     }
-    Status::Ok
 }
 
-async fn delete_lobby(lobby: &str, db: &mut Connection<Lobbies>) {
-    let lobby = get_hash_name(lobby);
-    let _: () = db.del(&lobby).await.unwrap();
-    let _: () = db.srem("matchmaker/lobbies", &lobby).await.unwrap();
+/// Restarts the expiry of the lobby, its players, reservations and invite code.
+/// Returns whether the lobby still exists.
+async fn refresh_lobby(
+    lobby: &str,
+    config: &MatchmakerConfig,
+    db: &mut Connection<Lobbies>,
+) -> bool {
+    let ttl = config.lobby_ttl as usize;
+    if !db.expire(get_hash_name(lobby), ttl).await.unwrap() {
+        return false;
+    }
+    let _: bool = db.expire(get_players_name(lobby), ttl).await.unwrap();
+    let _: bool = db.expire(get_reservations_name(lobby), ttl).await.unwrap();
+    let invite_code: Option<String> = db.hget(get_hash_name(lobby), "invite_code").await.unwrap();
+    if let Some(invite_code) = invite_code.and_then(lobby_invite_code) {
+        let _: bool = db.expire(get_invite_name(&invite_code), ttl).await.unwrap();
+    }
+    true
+}
+
+/// Lobby names are part of Redis keys, of the connect tokens and of the paths hosts and players use,
+/// so they must not collide with the keys of other lobbies, have to fit in the tokens
+/// and must not need to be encoded in URLs.
+fn is_valid_lobby_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(|c: char| matches!(c, '/' | ':' | '?' | '#' | '%') || c.is_whitespace())
+        && ConnectionData::is_valid_data_part(name)
+}
+
+/// Lobbies need room for at least one player, and no more than the clients can seat.
+fn is_valid_capacity(max_players: u8) -> bool {
+    (1..=MAX_PLAYERS).contains(&max_players)
+}
+
+/// Listed lobbies store an empty invite code.
+fn lobby_invite_code(invite_code: String) -> Option<String> {
+    Some(invite_code).filter(|code| !code.is_empty())
 }
 
 fn get_hash_name(lobby: &str) -> String {
     format!("matchmaker/lobby:{}", lobby)
 }
 
+/// Set of the usernames of the players in the lobby.
+fn get_players_name(lobby: &str) -> String {
+    format!("matchmaker/lobby:{}/players", lobby)
+}
+
+/// Name of the unlisted lobby that the invite code leads to.
+fn get_invite_name(invite_code: &str) -> String {
+    format!("matchmaker/invite:{}", invite_code.to_uppercase())
+}
+
+/// Sorted set of the usernames that were given a seat, scored by the unix time the seat expires.
+fn get_reservations_name(lobby: &str) -> String {
+    format!("matchmaker/lobby:{}/reservations", lobby)
+}
+
 pub(crate) fn get_routes() -> Vec<Route> {
     routes![
         list_lobbies,
         create_lobby,
         get_lobby,
-        set_player_count,
-        join_lobby
+        find_invite,
+        join_lobby,
+        register_server,
+        heartbeat,
+        report_event,
+        list_matches
     ]
 }
@@ -0,0 +1,31 @@
+use rocket::fairing::{AdHoc, Fairing};
+use rocket::serde::Deserialize;
+
+/// Settings of the matchmaker, read from `Rocket.toml` or `ROCKET_*` environment variables.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MatchmakerConfig {
+    /// Seconds until a connect token expires.
+    pub token_expiry: u64,
+    /// Seconds until a lobby expires, unless its host sends a heartbeat.
+    pub lobby_ttl: u64,
+    /// Seconds a seat stays reserved for a joining player until they connect to the server.
+    pub reservation_expiry: u64,
+    /// Seconds until a session expires and its user has to log in again.
+    pub session_ttl: u64,
+    /// Seconds between two sweeps of the reaper, which forgets expired lobbies.
+    pub reaper_interval: u64,
+}
+
+pub fn get_config_fairing() -> impl Fairing {
+    AdHoc::try_on_ignite("Matchmaker config", |rocket| async move {
+        let config: MatchmakerConfig = match rocket.figment().extract() {
+            Ok(config) => config,
+            Err(error) => {
+                error!("Invalid matchmaker config: {}", error);
+                return Err(rocket);
+            }
+        };
+        Ok(rocket.manage(config))
+    })
+}
@@ -2,14 +2,21 @@
 extern crate rocket;
 use rocket_db_pools::Database;
 
+mod accounts;
 mod client_api;
-mod server_connection;
+mod config;
 mod headers;
+mod reaper;
+mod secrets;
+mod server_connection;
 
 #[launch]
 fn rocket() -> _ {
     rocket::build()
+        .attach(config::get_config_fairing())
         .attach(client_api::Lobbies::init())
+        .attach(reaper::get_reaper_fairing())
         .attach(headers::get_cors_fairing())
         .mount("/", client_api::get_routes())
+        .mount("/", accounts::get_routes())
 }
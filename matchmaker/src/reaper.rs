@@ -0,0 +1,66 @@
+use rocket::fairing::{AdHoc, Fairing};
+use rocket::tokio::time::{self, Duration};
+use rocket_db_pools::deadpool_redis::{self, redis};
+use rocket_db_pools::Database;
+
+use crate::client_api::{Lobbies, LOBBY_SET};
+use crate::config::MatchmakerConfig;
+
+/// Removes every lobby from the set whose hash has expired. Returns how many were removed.
+/// KEYS: lobby set.
+const REAP_SCRIPT: &str = r"
+local removed = 0
+for _, lobby in ipairs(redis.call('SMEMBERS', KEYS[1])) do
+    if redis.call('EXISTS', lobby) == 0 then
+        redis.call('SREM', KEYS[1], lobby)
+        removed = removed + 1
+    end
+end
+return removed
+";
+
+/// Regularly forgets the lobbies whose hosts stopped sending heartbeats.
+/// Redis expires their hashes on its own, but not their membership in the lobby set.
+pub fn get_reaper_fairing() -> impl Fairing {
+    AdHoc::on_liftoff("Lobby reaper", |rocket| {
+        Box::pin(async move {
+            let pool = match Lobbies::fetch(rocket) {
+                Some(lobbies) => deadpool_redis::Pool::clone(lobbies),
+                None => {
+                    error!("The lobby reaper needs the lobbies database");
+                    return;
+                }
+            };
+            let interval = match rocket.state::<MatchmakerConfig>() {
+                Some(config) => Duration::from_secs(config.reaper_interval),
+                None => return,
+            };
+            rocket::tokio::spawn(run_reaper(pool, interval));
+        })
+    })
+}
+
+async fn run_reaper(pool: deadpool_redis::Pool, interval: Duration) {
+    let mut interval = time::interval(interval);
+    loop {
+        interval.tick().await;
+        let mut db = match pool.get().await {
+            Ok(db) => db,
+            Err(error) => {
+                warn!("The lobby reaper could not connect: {}", error);
+                continue;
+            }
+        };
+        let removed: Result<u64, _> = redis::cmd("EVAL")
+            .arg(REAP_SCRIPT)
+            .arg(1)
+            .arg(LOBBY_SET)
+            .query_async(&mut db)
+            .await;
+        match removed {
+            Ok(0) => {}
+            Ok(removed) => info!("Reaped {} expired lobbies", removed),
+            Err(error) => warn!("The lobby reaper failed: {}", error),
+        }
+    }
+}
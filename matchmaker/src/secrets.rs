@@ -0,0 +1,72 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::Rng;
+use renet::NETCODE_KEY_BYTES;
+use rocket::tokio::task;
+
+/// Letters and digits that cannot be confused with each other when read aloud or typed.
+const INVITE_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const INVITE_CODE_LENGTH: usize = 6;
+const TOKEN_BYTES: usize = 32;
+
+/// Hashes the password with Argon2 and a random salt. The result contains the salt and can be stored as is.
+/// Hashing takes a while on purpose, so it runs on a blocking thread instead of holding up other requests.
+pub async fn hash_password(password: &str) -> String {
+    let password = password.to_string();
+    task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    })
+    .await
+    .unwrap()
+}
+
+/// Whether the password matches a hash created by `hash_password`.
+pub async fn verify_password(password: &str, stored: &str) -> bool {
+    let (password, stored) = (password.to_string(), stored.to_string());
+    task::spawn_blocking(move || match PasswordHash::new(&stored) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    })
+    .await
+    .unwrap()
+}
+
+/// A short code that is easy to pass on to friends.
+pub fn generate_invite_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..INVITE_CODE_LENGTH)
+        .map(|_| char::from(INVITE_CODE_ALPHABET[rng.gen_range(0..INVITE_CODE_ALPHABET.len())]))
+        .collect()
+}
+
+/// Compares without stopping at the first difference, so the time taken does not give it away.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// A key for the connect tokens of a single lobby.
+pub fn generate_server_key() -> [u8; NETCODE_KEY_BYTES] {
+    rand::random()
+}
+
+/// A token that cannot be guessed, to identify a session.
+pub fn generate_session_token() -> String {
+    generate_token()
+}
+
+/// A token that cannot be guessed, which only the host of a lobby gets.
+pub fn generate_host_token() -> String {
+    generate_token()
+}
+
+fn generate_token() -> String {
+    let token: [u8; TOKEN_BYTES] = rand::random();
+    hex::encode(token)
+}
@@ -1,4 +1,5 @@
-use matchmaker_models::client_api::LobbyResponse;
+use crate::config::MatchmakerConfig;
+use matchmaker_models::client_api::{LobbyResponse, User};
 use matchmaker_models::server_api::*;
 use renet::ConnectToken;
 use renet::NETCODE_KEY_BYTES;
@@ -6,14 +7,18 @@ use std::net::SocketAddr;
 use std::time::SystemTime;
 use uuid::Uuid;
 
-const PRIVATE_KEY: &[u8; NETCODE_KEY_BYTES] = b"an example very very secret key."; // 32-bytes
-
-pub fn create_client_connection_data(lobby: &str, player: &str) -> LobbyResponse {
+pub fn create_client_connection_data(
+    config: &MatchmakerConfig,
+    server_addr: SocketAddr,
+    server_key: &[u8; NETCODE_KEY_BYTES],
+    lobby: &str,
+    user: &User,
+) -> LobbyResponse {
     // This probably eliminates uniqueness guarantee, let's see how that goes.
     let client_id = Uuid::new_v4().as_u64_pair().0;
 
-    let connection_data = ConnectionData::try_new(player, lobby).unwrap();
-    let token = generate_token(connection_data, client_id);
+    let connection_data = ConnectionData::try_new(user.id, &user.username, lobby).unwrap();
+    let token = generate_token(config, server_addr, server_key, connection_data, client_id);
     let token_bytes = serialize_connect_token(token);
 
     LobbyResponse {
@@ -28,8 +33,13 @@ fn serialize_connect_token(token: ConnectToken) -> Vec<u8> {
     bytes
 }
 
-fn generate_token(connection_data: ConnectionData, client_id: u64) -> ConnectToken {
-    let server_addr: SocketAddr = format!("127.0.0.1:1337").parse().unwrap();
+fn generate_token(
+    config: &MatchmakerConfig,
+    server_addr: SocketAddr,
+    server_key: &[u8; NETCODE_KEY_BYTES],
+    connection_data: ConnectionData,
+    client_id: u64,
+) -> ConnectToken {
     let current_time = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
@@ -37,12 +47,12 @@ fn generate_token(connection_data: ConnectionData, client_id: u64) -> ConnectTok
     ConnectToken::generate(
         current_time,
         PROTOCOL_ID,
-        300,
+        config.token_expiry,
         client_id,
         15,
         vec![server_addr],
         Some(&connection_data.to_netcode_user_data()),
-        PRIVATE_KEY,
+        server_key,
     )
     .unwrap()
 }
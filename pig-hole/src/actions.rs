@@ -29,7 +29,8 @@ fn set_mouse_actions(
         actions.selected_pig = None;
     }
     let window = windows.get_primary().expect("No primary window found");
-    if mouse_input.pressed(MouseButton::Left) {
+    // Only the click itself selects, holding the button must not pick further pigs
+    if mouse_input.just_pressed(MouseButton::Left) {
         actions.selected_pig = get_pig_under_cursor(trough_position_query, window);
     } else {
         actions.hovered_trough = get_pig_under_cursor(trough_position_query, window);
@@ -53,10 +54,12 @@ fn get_pig_under_cursor(
     if let Some(position) = get_cursor_world_position(window) {
         for (transform, pig) in trough_position_query.iter() {
             const RADIUS: f32 = 20.0;
-            if position.x <= transform.translation.x + RADIUS
-                && position.x >= transform.translation.x - RADIUS
-                && position.y <= transform.translation.y + RADIUS
-                && position.y >= transform.translation.y - RADIUS
+            // Boards that would not fit the window are scaled down
+            let radius = RADIUS * transform.scale.x;
+            if position.x <= transform.translation.x + radius
+                && position.x >= transform.translation.x - radius
+                && position.y <= transform.translation.y + radius
+                && position.y >= transform.translation.y - radius
             {
                 return Some(*pig);
             }
@@ -0,0 +1,338 @@
+use crate::board::{Pig, PigStatus};
+use crate::loading::{BoardAssetCreator, FontAssets};
+use crate::pig_collection::{get_relative_position_of_nth_pig, PigCollection};
+use crate::player::Player;
+use crate::{GameState, MatchEntity};
+use bevy::prelude::*;
+use rand::Rng;
+use rules::Phase;
+
+/// Seconds the dice tumble before showing the roll.
+const TUMBLE_DURATION: f32 = 0.5;
+/// Seconds between two faces shown while tumbling.
+const FACE_DURATION: f32 = 0.06;
+/// Seconds a pig needs to move between a trough and a pig collection.
+const FLIGHT_DURATION: f32 = 0.4;
+const DICE_POSITION: [f32; 3] = [0.0, -270.0, 5.0];
+/// Pigs on the move are drawn above everything on the board.
+const FLIGHT_HEIGHT: f32 = 10.0;
+
+pub struct AnimationPlugin;
+
+/// This plugin animates the changes of the game: dice tumble before showing the roll,
+/// and pigs move between the pig collections and the troughs.
+/// The players only catch up with the game once every animation has finished.
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Animations>()
+            .add_event::<AnimationFinished>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_animations)
+                    .with_system(spawn_dice),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(start_animations.label(StartAnimations))
+                    .with_system(tumble_dice)
+                    .with_system(fly_pigs),
+            );
+    }
+}
+
+/// Systems that have to see the animations of a change of the game run after this label.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StartAnimations;
+
+/// Sent whenever an animation has ended.
+pub struct AnimationFinished;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Animations {
+    running: usize,
+    /// The game as it was when the last animations started.
+    shown_game: Option<rules::GameState>,
+}
+
+impl Animations {
+    pub fn is_running(&self) -> bool {
+        self.running > 0
+    }
+
+    fn finish(&mut self, finished_events: &mut EventWriter<AnimationFinished>) {
+        self.running = self.running.saturating_sub(1);
+        finished_events.send(AnimationFinished);
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Component)]
+struct Dice;
+
+#[derive(Debug, Clone, Component)]
+struct Tumble {
+    face: u8,
+    /// Lowest and highest face shown while tumbling.
+    faces: (u8, u8),
+    timer: Timer,
+    face_timer: Timer,
+}
+
+#[derive(Debug, Clone, Component)]
+struct Flight {
+    from: Transform,
+    to: Transform,
+    timer: Timer,
+}
+
+fn reset_animations(mut animations: ResMut<Animations>) {
+    *animations = default();
+}
+
+fn spawn_dice(
+    mut commands: Commands,
+    board_assets: Res<BoardAssetCreator>,
+    font_assets: Res<FontAssets>,
+) {
+    commands
+        .spawn_bundle(board_assets.get_dice())
+        .insert(Transform::from_translation(DICE_POSITION.into()))
+        .insert(Visibility { is_visible: false })
+        .insert(Name::new("Dice"))
+        .insert(MatchEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(Text2dBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: 30.0,
+                            color: Color::BLACK,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                    ..default()
+                })
+                .insert(Dice);
+        });
+}
+
+/// Compares the game to the one shown before and animates the move in between.
+/// Changes of more than a single move, like syncs or skipping through a replay, are not animated.
+#[allow(clippy::too_many_arguments)]
+fn start_animations(
+    mut commands: Commands,
+    mut animations: ResMut<Animations>,
+    game: Option<Res<rules::GameState>>,
+    board_assets: Res<BoardAssetCreator>,
+    mut pig_query: Query<(&mut Pig, &GlobalTransform)>,
+    player_query: Query<(Entity, &Player)>,
+    pig_collection_query: Query<(&Parent, &PigCollection, &GlobalTransform)>,
+    mut collection_pig_query: Query<(&GlobalTransform, &mut Visibility), Without<Pig>>,
+    dice_query: Query<&Parent, With<Dice>>,
+) {
+    let game = match game {
+        Some(game) if game.is_changed() => game,
+        _ => return,
+    };
+    let previous = match animations.shown_game.replace(game.clone()) {
+        Some(previous) if previous.player_count() == game.player_count() => previous,
+        _ => return,
+    };
+
+    // Taking back a placement also returns to placing, but changes the board
+    let is_roll = previous.phase() == Phase::Rolling
+        && previous.current_player() == game.current_player()
+        && previous.board() == game.board();
+    let rolled_face = match game.phase() {
+        Phase::PlacingInGroup(face) | Phase::CollectingGroup(face) if is_roll => Some(face),
+        _ => None,
+    };
+    if let Some(face) = rolled_face {
+        let rules = game.rules();
+        for parent in dice_query.iter() {
+            commands.entity(parent.0).insert(Tumble {
+                face,
+                faces: (rules.lowest_roll(), rules.highest_roll()),
+                timer: Timer::from_seconds(TUMBLE_DURATION, false),
+                face_timer: Timer::from_seconds(FACE_DURATION, true),
+            });
+            animations.running += 1;
+        }
+        return;
+    }
+
+    // A single move changes the pigs of a single player
+    let pig_changes: Vec<i64> = (0..game.player_count())
+        .map(|index| i64::from(game.pig_count(index)) - i64::from(previous.pig_count(index)))
+        .collect();
+    let mut changed_players = (0..game.player_count()).filter(|&index| pig_changes[index] != 0);
+    let (player_index, pig_change) = match (changed_players.next(), changed_players.next()) {
+        (Some(index), None) => (index, pig_changes[index]),
+        _ => return,
+    };
+    let (placed, removed): (Vec<_>, Vec<_>) = game
+        .board()
+        .troughs()
+        .filter(|&trough| game.board().is_occupied(trough) != previous.board().is_occupied(trough))
+        .partition(|&trough| game.board().is_occupied(trough));
+    if !removed
+        .windows(2)
+        .all(|pair| pair[0].group == pair[1].group)
+    {
+        return;
+    }
+    let pig_hole = rules::Trough {
+        group: game.rules().highest_roll(),
+        index: 1,
+    };
+
+    let player = player_query
+        .iter()
+        .find(|(_, player)| player.index == player_index)
+        .map(|(entity, _)| entity);
+    let collection = pig_collection_query
+        .iter()
+        .find(|(parent, _, _)| Some(parent.0) == player);
+    let (collection, collection_transform) = match collection {
+        Some((_, collection, transform)) => (collection, *transform),
+        None => return,
+    };
+    if pig_change < 0 {
+        if pig_change != -1 || placed.len() > 1 || !removed.is_empty() {
+            return;
+        }
+        let target = placed.first().copied().unwrap_or(pig_hole);
+        let to = match find_pig(&mut pig_query, target) {
+            Some((_, transform)) => *transform,
+            None => return,
+        };
+        // Pigs leave the collection from its end, like when the collection is synced
+        let from = collection.pigs.iter().rev().find_map(|&entity| {
+            match collection_pig_query.get_mut(entity) {
+                Ok((transform, mut visibility)) if visibility.is_visible => {
+                    visibility.is_visible = false;
+                    Some(*transform)
+                }
+                _ => None,
+            }
+        });
+        if let Some(from) = from {
+            spawn_flying_pig(&mut commands, &mut animations, &board_assets, from, to);
+        }
+    } else {
+        // Pigs are collected from a full group, or a placement was taken back
+        let sources = if removed.is_empty() {
+            vec![pig_hole]
+        } else {
+            removed
+        };
+        if !placed.is_empty() || sources.len() as i64 != pig_change {
+            return;
+        }
+        for (n, trough) in sources.into_iter().enumerate() {
+            let from = match find_pig(&mut pig_query, trough) {
+                Some((mut pig, transform)) => {
+                    // The pig leaves the trough right away, instead of once the game is synced
+                    pig.status = PigStatus::Empty;
+                    *transform
+                }
+                None => continue,
+            };
+            let position =
+                get_relative_position_of_nth_pig((collection.pigs.len() + n) as u32, &board_assets);
+            let to = collection_transform.mul_transform(Transform::from_translation(position));
+            spawn_flying_pig(&mut commands, &mut animations, &board_assets, from, to);
+        }
+    }
+}
+
+fn find_pig<'a>(
+    pig_query: &'a mut Query<(&mut Pig, &GlobalTransform)>,
+    trough: rules::Trough,
+) -> Option<(Mut<'a, Pig>, &'a GlobalTransform)> {
+    pig_query
+        .iter_mut()
+        .find(|(pig, _)| rules::Trough::from(pig.trough) == trough)
+}
+
+fn spawn_flying_pig(
+    commands: &mut Commands,
+    animations: &mut Animations,
+    board_assets: &BoardAssetCreator,
+    from: GlobalTransform,
+    to: GlobalTransform,
+) {
+    let mut from = Transform::from(from);
+    let mut to = Transform::from(to);
+    from.translation.z = FLIGHT_HEIGHT;
+    to.translation.z = FLIGHT_HEIGHT;
+    commands
+        .spawn_bundle(board_assets.get_pig())
+        .insert(from)
+        .insert(Flight {
+            from,
+            to,
+            timer: Timer::from_seconds(FLIGHT_DURATION, false),
+        })
+        .insert(Name::new("Flying pig"))
+        .insert(MatchEntity);
+    animations.running += 1;
+}
+
+fn tumble_dice(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animations: ResMut<Animations>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut dice_query: Query<(Entity, &mut Tumble, &mut Visibility, &Children)>,
+    mut text_query: Query<&mut Text, With<Dice>>,
+) {
+    for (entity, mut tumble, mut visibility, children) in dice_query.iter_mut() {
+        visibility.is_visible = true;
+        let tumbling = !tumble.timer.tick(time.delta()).finished();
+        let face = if tumbling {
+            if !tumble.face_timer.tick(time.delta()).just_finished() {
+                continue;
+            }
+            let (lowest, highest) = tumble.faces;
+            rand::thread_rng().gen_range(lowest..=highest)
+        } else {
+            tumble.face
+        };
+        for &child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.sections[0].value = face.to_string();
+            }
+        }
+        if !tumbling {
+            commands.entity(entity).remove::<Tumble>();
+            animations.finish(&mut finished_events);
+        }
+    }
+}
+
+fn fly_pigs(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animations: ResMut<Animations>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut flight_query: Query<(Entity, &mut Flight, &mut Transform)>,
+) {
+    for (entity, mut flight, mut transform) in flight_query.iter_mut() {
+        flight.timer.tick(time.delta());
+        let progress = flight.timer.percent();
+        // Starts and lands slowly
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        transform.translation = flight.from.translation.lerp(flight.to.translation, eased);
+        transform.scale = flight.from.scale.lerp(flight.to.scale, eased);
+        if flight.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            animations.finish(&mut finished_events);
+        }
+    }
+}
@@ -11,6 +11,7 @@ impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
             .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_audio))
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(stop_audio))
             .add_system_set(
                 SystemSet::on_update(GameState::Playing).with_system(control_flying_sound),
             );
@@ -23,6 +24,10 @@ fn start_audio(audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
     audio.pause();
 }
 
+fn stop_audio(audio: Res<Audio>) {
+    audio.stop();
+}
+
 fn control_flying_sound(_actions: Res<Actions>, _audio: Res<Audio>) {
     /*
     if actions.player_movement.is_some() {
@@ -1,15 +1,16 @@
 use std::fmt::Display;
 
-use crate::loading::BoardAssetCreator;
+use crate::loading::{BoardAssetCreator, BoardLayout, BoardLayoutAssets, BoardLayouts};
 use crate::player::Player;
 use crate::player::PlayerState;
-use crate::GameState;
+use crate::{GameState, MatchEntity};
 use bevy::prelude::*;
 #[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 #[cfg(feature = "dev")]
 use bevy_inspector_egui::RegisterInspectable;
-use bevy_prototype_lyon::prelude::*;
+use bevy_prototype_lyon::{entity::ShapeBundle, prelude::*};
+use rules::RuleSet;
 
 #[cfg_attr(feature = "dev", derive(Inspectable))]
 #[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Debug, Hash, Component)]
@@ -52,6 +53,15 @@ impl From<(u8, u8)> for Trough {
     }
 }
 
+impl From<Trough> for rules::Trough {
+    fn from(trough: Trough) -> Self {
+        Self {
+            group: trough.group,
+            index: trough.index,
+        }
+    }
+}
+
 impl Display for Trough {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}.{}", self.group, self.index)
@@ -68,9 +78,6 @@ impl Trough {
         if index > group {
             panic!("inner cannot be greater than outer");
         }
-        if group > 6 {
-            panic!("outer cannot be greater than 6");
-        }
         if group == 0 {
             panic!("outer cannot be 0");
         }
@@ -100,25 +107,29 @@ impl Plugin for BoardPlugin {
     }
 }
 
-fn spawn_board(mut commands: Commands, board_assets: Res<BoardAssetCreator>) {
-    let board_size = Vec3::new(
-        board_assets.get_board_extents().x,
-        board_assets.get_board_extents().y,
-        0.0,
-    );
-    let quadrant_translation = Vec2::new(board_size.x / 2., board_size.y / 2.);
-    let padding = board_assets.get_board_padding();
-    let quadrant_offset = board_size / 2. + padding;
-
-    let inner_padding = 5.;
-    let offset_right = quadrant_translation / 5. + inner_padding;
-    let offset_left = Vec2::new(-offset_right.x, offset_right.y);
+fn spawn_board(
+    mut commands: Commands,
+    board_assets: Res<BoardAssetCreator>,
+    board_layout_assets: Res<BoardLayoutAssets>,
+    board_layouts: Res<Assets<BoardLayouts>>,
+    rules: Res<RuleSet>,
+) {
+    let layout = match board_layouts.get(&board_layout_assets.layouts) {
+        Some(layouts) => layouts.for_rules(&rules),
+        None => BoardLayout::generate(&rules),
+    };
+    info!("Playing on the {} board", layout.name);
 
     commands
         .spawn()
         .insert(Name::new("Board"))
+        .insert(MatchEntity)
         .insert(GlobalTransform::default())
-        .insert(Transform::default())
+        .insert(Transform::from_scale(Vec3::new(
+            layout.scale,
+            layout.scale,
+            1.,
+        )))
         .with_children(|parent| {
             parent
                 .spawn()
@@ -126,155 +137,30 @@ fn spawn_board(mut commands: Commands, board_assets: Res<BoardAssetCreator>) {
                 .insert(GlobalTransform::default())
                 .insert(Transform::default())
                 .with_children(|parent| {
-                    parent
-                        .spawn_bundle(board_assets.get_border())
-                        .insert(Name::new("Border"));
-                });
-            create_trough(parent, Trough::new(6, 1), Vec2::new(0., 0.), &board_assets);
-
-            let top_right_offset = quadrant_offset;
-            parent
-                .spawn()
-                .insert(Name::new("Top right"))
-                .insert(GlobalTransform::default())
-                .insert(Transform::from_translation(top_right_offset))
-                .with_children(|parent| {
-                    create_trough(parent, Trough::new(1, 1), -2. * offset_right, &board_assets);
-
-                    parent
-                        .spawn()
-                        .insert(Name::new("Trough 2"))
-                        .insert(GlobalTransform::default())
-                        .insert(Transform::default())
-                        .with_children(|parent| {
-                            create_trough(parent, Trough::new(2, 1), Vec2::ZERO, &board_assets);
-                            create_trough(
-                                parent,
-                                Trough::new(2, 2),
-                                2. * offset_right,
-                                &board_assets,
-                            );
-                        });
-                });
-
-            let top_left_offset = Vec3::new(-quadrant_offset.x, quadrant_offset.y, 0.);
-            parent
-                .spawn()
-                .insert(Name::new("Top left"))
-                .insert(GlobalTransform::default())
-                .insert(Transform::from_translation(top_left_offset))
-                .with_children(|parent| {
-                    parent
-                        .spawn()
-                        .insert(Name::new("Trough 3"))
-                        .insert(GlobalTransform::default())
-                        .insert(Transform::default())
-                        .with_children(|parent| {
-                            create_trough(
-                                parent,
-                                Trough::new(3, 1),
-                                2. * offset_left,
-                                &board_assets,
-                            );
-
-                            create_trough(parent, Trough::new(3, 2), Vec2::ZERO, &board_assets);
-
-                            create_trough(
-                                parent,
-                                Trough::new(3, 3),
-                                -2. * offset_left,
-                                &board_assets,
-                            );
-                        });
-                });
-
-            let bottom_left_offset = Vec3::new(-quadrant_offset.x, -quadrant_offset.y, 0.);
-            parent
-                .spawn()
-                .insert(Name::new("Bottom left"))
-                .insert(GlobalTransform::default())
-                .insert(Transform::from_translation(bottom_left_offset))
-                .with_children(|parent| {
-                    parent
-                        .spawn()
-                        .insert(Name::new("Trough 4"))
-                        .insert(GlobalTransform::default())
-                        .insert(Transform::default())
-                        .with_children(|parent| {
-                            create_trough(
-                                parent,
-                                Trough::new(4, 1),
-                                2. * offset_right,
-                                &board_assets,
-                            );
-
-                            create_trough(
-                                parent,
-                                Trough::new(4, 2),
-                                2. * offset_left,
-                                &board_assets,
-                            );
-
-                            create_trough(
-                                parent,
-                                Trough::new(4, 3),
-                                -2. * offset_right,
-                                &board_assets,
-                            );
-
-                            create_trough(
-                                parent,
-                                Trough::new(4, 4),
-                                -2. * offset_left,
-                                &board_assets,
-                            );
-                        });
+                    for &(width, height) in &layout.borders {
+                        parent
+                            .spawn_bundle(board_assets.get_border(Vec2::new(width, height)))
+                            .insert(Name::new("Border"));
+                    }
                 });
-
-            let bottom_right_offset = Vec3::new(quadrant_offset.x, -quadrant_offset.y, 0.);
-            parent
-                .spawn()
-                .insert(Name::new("Bottom right"))
-                .insert(GlobalTransform::default())
-                .insert(Transform::from_translation(bottom_right_offset))
-                .with_children(|parent| {
-                    parent
-                        .spawn()
-                        .insert(Name::new("Trough 5"))
-                        .insert(GlobalTransform::default())
-                        .insert(Transform::default())
-                        .with_children(|parent| {
-                            create_trough(
-                                parent,
-                                Trough::new(5, 1),
-                                2. * offset_right,
-                                &board_assets,
-                            );
-
+            for group in &layout.groups {
+                parent
+                    .spawn()
+                    .insert(Name::new(format!("Trough {}", group.group)))
+                    .insert(GlobalTransform::default())
+                    .insert(Transform::default())
+                    .with_children(|parent| {
+                        for (index, &(x, y)) in (1..).zip(&group.troughs) {
                             create_trough(
                                 parent,
-                                Trough::new(5, 2),
-                                2. * offset_left,
+                                Trough::new(group.group, index),
+                                Vec2::new(x, y),
+                                board_assets.get_trough(group.fill, group.outline),
                                 &board_assets,
                             );
-
-                            create_trough(
-                                parent,
-                                Trough::new(5, 3),
-                                -2. * offset_right,
-                                &board_assets,
-                            );
-
-                            create_trough(
-                                parent,
-                                Trough::new(5, 4),
-                                -2. * offset_left,
-                                &board_assets,
-                            );
-
-                            create_trough(parent, Trough::new(5, 5), Vec2::ZERO, &board_assets);
-                        });
-                });
+                        }
+                    });
+            }
         });
 }
 
@@ -282,10 +168,11 @@ fn create_trough(
     parent: &mut ChildBuilder,
     trough: Trough,
     position: Vec2,
+    bundle: ShapeBundle,
     board_assets: &Res<BoardAssetCreator>,
 ) {
     parent
-        .spawn_bundle(board_assets.get_trough_for_group(trough.group))
+        .spawn_bundle(bundle)
         .insert(Transform::from_translation(Vec3::new(
             position.x, position.y, 1.,
         )))
@@ -307,11 +194,8 @@ fn create_trough(
         });
 }
 
-fn update_pig_visibility(mut pig_query: Query<(&mut Pig, &mut DrawMode, &mut Visibility)>) {
-    for (mut pig, mut draw_mode, mut visibility) in pig_query.iter_mut() {
-        if pig.trough.group == 6 && pig.is_occupied() {
-            pig.status = PigStatus::Empty;
-        }
+fn update_pig_visibility(mut pig_query: Query<(&Pig, &mut DrawMode, &mut Visibility)>) {
+    for (pig, mut draw_mode, mut visibility) in pig_query.iter_mut() {
         match pig.status {
             PigStatus::Empty => visibility.is_visible = false,
             PigStatus::Occupied => {
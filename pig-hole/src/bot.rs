@@ -0,0 +1,63 @@
+use crate::animation::Animations;
+use crate::networking::run_if_offline;
+use crate::player::{Intent, Player};
+use crate::GameState;
+use bevy::prelude::*;
+use rules::bot::{self, Difficulty};
+
+/// Seconds a bot waits before each move, so humans can follow what it does.
+const MOVE_DELAY: f32 = 0.6;
+
+pub struct BotPlugin;
+
+/// This plugin lets computer opponents play their turns.
+/// Bots only run in offline games, where this machine runs the rules.
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotTimer>().add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_run_criteria(run_if_offline)
+                .with_system(play_bot_turn),
+        );
+    }
+}
+
+/// Marks players whose moves are decided by the computer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component)]
+pub struct Bot(pub Difficulty);
+
+struct BotTimer(Timer);
+
+impl Default for BotTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(MOVE_DELAY, true))
+    }
+}
+
+fn play_bot_turn(
+    time: Res<Time>,
+    mut timer: ResMut<BotTimer>,
+    mut intents: EventWriter<Intent>,
+    game: Res<rules::GameState>,
+    animations: Res<Animations>,
+    bot_query: Query<(&Player, &Bot)>,
+) {
+    // Bots wait for the last move to be shown, like humans do
+    if animations.is_running() {
+        return;
+    }
+    // Ask the game rather than `Turn`, which only catches up once the game has been synced
+    let difficulty = match bot_query
+        .iter()
+        .find(|(player, _)| player.index == game.current_player())
+    {
+        Some((_, Bot(difficulty))) => *difficulty,
+        None => return,
+    };
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    if let Some(decision) = bot::decide(&game, difficulty, &mut rand::thread_rng()) {
+        intents.send(decision.into());
+    }
+}
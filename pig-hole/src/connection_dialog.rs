@@ -0,0 +1,61 @@
+use crate::networking::{self, ConnectionInfo, ConnectionLost, ReconnectEvent, Reconnecting};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+pub struct ConnectionDialogPlugin;
+
+/// This plugin lets players decide what to do when the connection to the match breaks down:
+/// join the match again or go back to the menu.
+impl Plugin for ConnectionDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(show_connection_dialog),
+        );
+    }
+}
+
+fn show_connection_dialog(
+    mut commands: Commands,
+    mut egui_ctx: ResMut<EguiContext>,
+    mut state: ResMut<State<GameState>>,
+    mut reconnect_events: EventWriter<ReconnectEvent>,
+    connection_lost: Option<Res<ConnectionLost>>,
+    reconnecting: Option<Res<Reconnecting>>,
+    info: Option<Res<ConnectionInfo>>,
+) {
+    if connection_lost.is_none() && reconnecting.is_none() {
+        return;
+    }
+    egui::Window::new("Connection lost")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                if reconnecting.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.label("Reconnecting...");
+                        ui.spinner();
+                    });
+                    return;
+                }
+                if let Some(ConnectionLost(error)) = connection_lost.as_deref() {
+                    ui.label(error.to_string());
+                }
+                ui.add_space(20.0);
+                ui.horizontal(|ui| {
+                    // Only clients can join again, the server of a host is gone for good
+                    if info.is_some() && ui.button("Reconnect").clicked() {
+                        reconnect_events.send(ReconnectEvent);
+                    }
+                    if ui.button("Leave").clicked() {
+                        networking::go_offline(&mut commands);
+                        if let Err(error) = state.set(GameState::Menu) {
+                            warn!("Failed to leave the match: {:?}", error);
+                        }
+                    }
+                });
+            });
+        });
+}
@@ -0,0 +1,74 @@
+use crate::player::LocalPlayer;
+use crate::turn::{Turn, TurnChangeEvent};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+pub struct HandoverPlugin;
+
+/// This plugin makes sure that in a hot-seat game the next player is in front of the screen
+/// before their turn starts. Until then, no player input is accepted.
+/// Nothing happens if only one player is playing on this machine.
+impl Plugin for HandoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Handover>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_handover))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(start_handover)
+                    .with_system(show_handover),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Handover {
+    /// Name of the player the screen should be passed to.
+    pending: Option<String>,
+}
+
+impl Handover {
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+fn reset_handover(mut handover: ResMut<Handover>) {
+    *handover = default();
+}
+
+fn start_handover(
+    mut turn_change_event_reader: EventReader<TurnChangeEvent>,
+    mut handover: ResMut<Handover>,
+    turn: Res<Turn>,
+    player_query: Query<&Name, With<LocalPlayer>>,
+) {
+    for _event in turn_change_event_reader.iter() {
+        if player_query.iter().count() < 2 {
+            continue;
+        }
+        if let Ok(name) = player_query.get(turn.get_current_player()) {
+            handover.pending = Some(name.as_str().to_string());
+        }
+    }
+}
+
+fn show_handover(mut egui_ctx: ResMut<EguiContext>, mut handover: ResMut<Handover>) {
+    let name = match &handover.pending {
+        Some(name) => name.clone(),
+        None => return,
+    };
+    egui::Window::new("Next player")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(format!("Pass the game to {}", name));
+                ui.add_space(20.0);
+                if ui.button("Start turn").clicked() {
+                    handover.pending = None;
+                }
+            });
+        });
+}
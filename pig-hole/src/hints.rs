@@ -0,0 +1,127 @@
+use crate::player::Player;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use bevy_egui::{egui, EguiContext};
+use rules::analysis;
+use rules::bot::Difficulty;
+use std::sync::{Arc, RwLock};
+
+/// Games played to the end for every estimate of the win probabilities.
+const SAMPLES: usize = 200;
+
+pub struct HintsPlugin;
+
+/// This plugin shows the odds of rolling again when toggled with `H`.
+impl Plugin for HintsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hints>().add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(toggle_hints)
+                .with_system(estimate_win_probabilities)
+                .with_system(show_hints),
+        );
+    }
+}
+
+/// Win probabilities that are estimated in the background, since playing out games takes a while.
+type PendingEstimate = Arc<RwLock<Option<Vec<f64>>>>;
+
+#[derive(Debug, Clone, Default)]
+struct Hints {
+    enabled: bool,
+    /// Win probability of every seat, estimated for a recent state of the game.
+    /// Empty until the first estimate is done.
+    win_probabilities: Vec<f64>,
+    /// The estimate that is running, if any.
+    estimate: Option<PendingEstimate>,
+    /// The game changed since the last estimate was started.
+    outdated: bool,
+}
+
+fn toggle_hints(keyboard_input: Res<Input<KeyCode>>, mut hints: ResMut<Hints>) {
+    if keyboard_input.just_pressed(KeyCode::H) {
+        hints.enabled = !hints.enabled;
+        hints.win_probabilities.clear();
+        hints.estimate = None;
+    }
+}
+
+/// Estimates in the background, one after another, so changes of the game during an estimate
+/// are only picked up by the next one.
+fn estimate_win_probabilities(
+    mut hints: ResMut<Hints>,
+    game: Res<rules::GameState>,
+    task_pool: Res<AsyncComputeTaskPool>,
+) {
+    if !hints.enabled {
+        return;
+    }
+    if game.is_changed() {
+        hints.outdated = true;
+    }
+    let finished = hints
+        .estimate
+        .as_ref()
+        .map(|estimate| estimate.write().unwrap().take());
+    match finished {
+        Some(Some(win_probabilities)) => {
+            hints.win_probabilities = win_probabilities;
+            hints.estimate = None;
+        }
+        Some(None) => return,
+        None => {}
+    }
+    if !hints.outdated && !hints.win_probabilities.is_empty() {
+        return;
+    }
+    hints.outdated = false;
+    let estimate = PendingEstimate::default();
+    hints.estimate = Some(estimate.clone());
+    let game = game.clone();
+    task_pool
+        .spawn(async move {
+            let win_probabilities = analysis::win_probabilities(
+                &game,
+                Difficulty::Expectation,
+                SAMPLES,
+                &mut rand::thread_rng(),
+            );
+            *estimate.write().unwrap() = Some(win_probabilities);
+        })
+        .detach();
+}
+
+fn show_hints(
+    mut egui_ctx: ResMut<EguiContext>,
+    hints: Res<Hints>,
+    game: Res<rules::GameState>,
+    player_query: Query<(&Player, &Name)>,
+) {
+    if !hints.enabled {
+        return;
+    }
+    egui::Window::new("Hints")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::LEFT_TOP, egui::Vec2::new(10.0, 10.0))
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.label(format!(
+                "Next roll forces a collection: {:.0}%",
+                analysis::collection_probability(&game) * 100.0
+            ));
+            ui.label(format!(
+                "Expected pigs from rolling: {:+.2}",
+                analysis::expected_pig_delta(&game)
+            ));
+            ui.add_space(10.0);
+            ui.label("Chance to win:");
+            let mut players: Vec<_> = player_query.iter().collect();
+            players.sort_by_key(|(player, _)| player.index);
+            for (player, name) in players {
+                if let Some(probability) = hints.win_probabilities.get(player.index) {
+                    ui.label(format!("{}: {:.0}%", name.as_str(), probability * 100.0));
+                }
+            }
+        });
+}
@@ -1,7 +1,8 @@
 use crate::loading::FontAssets;
+use crate::networking::{DiceAudit, TurnClockState};
 use crate::player::{Player, PlayerInteractionModel, PlayerState};
-use crate::turn::Turn;
-use crate::GameState;
+use crate::turn::{IsOnTurn, Turn};
+use crate::{GameState, MatchEntity};
 use bevy::prelude::*;
 
 pub struct IngameMenuPlugin;
@@ -151,6 +152,7 @@ fn setup_menu(
             ..default()
         })
         .insert(Name::new("Ingame menu"))
+        .insert(MatchEntity)
         .with_children(|parent| {
             spawn_button(
                 parent,
@@ -359,32 +361,44 @@ fn handle_button_interaction(
 }
 
 fn update_info_text(
-    player_query: Query<&Player>,
+    player_query: Query<(&Player, &Name), With<IsOnTurn>>,
     mut view_model: ResMut<ViewModel>,
     turn: Res<Turn>,
+    game: Res<rules::GameState>,
+    dice_audit: Res<DiceAudit>,
+    turn_clock: Res<TurnClockState>,
+    time: Res<Time>,
 ) {
     let lines = &mut view_model.info_text_box.text_lines;
-    lines[0] = match turn.get_min_actions() {
+    lines[0] = match game.mandatory_rolls() {
         Some(min) => match turn.get_turn_number() {
             1 => format!("Turn {}, everyone needs to roll {} time\n", 1, min),
             turn => format!("Turn {}, everyone needs to roll {} times\n", turn, min),
         },
         None => format!("Turn {}\n", turn.get_turn_number()),
     };
-    for player in player_query.iter() {
+    let pig_hole = game.rules().highest_roll();
+    for (player, name) in player_query.iter() {
+        if turn.get_player_order().len() > 1 {
+            let time_left = match turn_clock.seconds_left(time.seconds_since_startup()) {
+                Some(seconds) => format!(", {:.0}s left", seconds.ceil()),
+                None => String::new(),
+            };
+            lines[0] = format!("{}{} is on turn{}\n", lines[0], name.as_str(), time_left);
+        }
         match player.state {
             PlayerState::PlacingInGroup(group) => {
-                lines[1] = get_roll_info_text(group);
-                lines[2] = match turn.get_min_actions() {
+                lines[1] = get_roll_info_text(group, pig_hole);
+                lines[2] = match game.mandatory_rolls() {
                     Some(min) => {
                         if player.action_count == min - 1 {
-                            if group == 6 {
+                            if group == pig_hole {
                                 "Place a pig in the pig hole to end your turn\n".to_string()
                             } else {
                                 "Place a pig in a trough to end your turn\n".to_string()
                             }
                         } else {
-                            if group == 6 {
+                            if group == pig_hole {
                                 "Place a pig in the pig hole\n".to_string()
                             } else {
                                 "Place a pig in a trough\n".to_string()
@@ -392,7 +406,7 @@ fn update_info_text(
                         }
                     }
                     None => {
-                        if group == 6 {
+                        if group == pig_hole {
                             "Place a pig in the pig hole\n".to_string()
                         } else {
                             "Place a pig in one of the corresponding troughs\n".to_string()
@@ -401,21 +415,25 @@ fn update_info_text(
                 }
             }
             PlayerState::CollectingGroup(group) => {
-                lines[1] = get_roll_info_text(group);
+                lines[1] = get_roll_info_text(group, pig_hole);
                 lines[2] = "The troughs are full. Collect the pigs to end your turn\n".to_string();
             }
             PlayerState::Thinking() => {
-                lines[1] = match turn.get_min_actions() {
-                    Some(min) => {
-                        let actions_left = min - player.action_count;
+                lines[1] = match game.mandatory_rolls() {
+                    Some(_) => {
+                        let actions_left = game.rolls_remaining();
                         match actions_left {
                             1 => "You need to roll 1 more time\n".to_string(),
                             _ => format!("You need to roll {} more times\n", actions_left),
                         }
                     }
-                    None => match player.action_count {
-                        0 => "You need to roll at least once\n".to_string(),
-                        _ => format!("Roll as much as you want, then end your turn\n"),
+                    None => match (player.action_count, game.roll_limit()) {
+                        (0, _) => "You need to roll at least once\n".to_string(),
+                        (count, Some(limit)) => format!(
+                            "Roll up to {} more times, then end your turn\n",
+                            limit.saturating_sub(count)
+                        ),
+                        (_, None) => format!("Roll as much as you want, then end your turn\n"),
                     },
                 };
                 lines[2] = " ".to_string();
@@ -426,21 +444,29 @@ fn update_info_text(
                 lines[2] = " ".to_string();
             }
             PlayerState::Won() => {
-                lines[1] = "You won!".to_string();
-                lines[2] = " ".to_string();
+                lines[1] = format!("{} won!", name.as_str());
+                lines[2] = get_dice_audit_text(&dice_audit);
             }
             PlayerState::Lost() => {
                 lines[1] = "You lost".to_string();
-                lines[2] = " ".to_string();
+                lines[2] = get_dice_audit_text(&dice_audit);
             }
         }
     }
 }
 
-fn get_roll_info_text(roll: u8) -> String {
-    if roll == 6 {
+fn get_roll_info_text(roll: u8, highest_roll: u8) -> String {
+    if roll == highest_roll {
         format!("You rolled a {}!\n", roll)
     } else {
         format!("You rolled a {}\n", roll)
     }
 }
+
+fn get_dice_audit_text(dice_audit: &DiceAudit) -> String {
+    match &dice_audit.result {
+        Some(Ok(())) => "All dice rolls were verified to be fair".to_string(),
+        Some(Err(error)) => format!("The dice were manipulated: {}", error),
+        None => " ".to_string(),
+    }
+}
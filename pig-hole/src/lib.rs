@@ -1,7 +1,12 @@
 mod actions;
+mod animation;
 mod audio;
 mod board;
+mod bot;
+mod connection_dialog;
 mod dev;
+mod handover;
+mod hints;
 mod ingame_menu;
 mod loading;
 mod menu;
@@ -9,19 +14,31 @@ mod networking;
 mod pig_collection;
 mod player;
 mod player_creation;
+mod replay;
+mod save;
 mod turn;
+mod undo;
 
 use crate::actions::ActionsPlugin;
+use crate::animation::AnimationPlugin;
 use crate::audio::InternalAudioPlugin;
 use crate::board::BoardPlugin;
+use crate::bot::BotPlugin;
+use crate::connection_dialog::ConnectionDialogPlugin;
 use crate::dev::DevPlugin;
+use crate::handover::HandoverPlugin;
+use crate::hints::HintsPlugin;
+use crate::ingame_menu::IngameMenuPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
 use crate::networking::NetworkingPlugin;
 use crate::pig_collection::PigCollectionPlugin;
 use crate::player::PlayerPlugin;
 use crate::player_creation::PlayerCreationPlugin;
+use crate::replay::ReplayPlugin;
+use crate::save::SavePlugin;
 use crate::turn::TurnPlugin;
+use crate::undo::UndoPlugin;
 
 use bevy::app::App;
 use bevy::prelude::*;
@@ -49,11 +66,32 @@ impl Plugin for GamePlugin {
             .add_plugin(ActionsPlugin)
             .add_plugin(InternalAudioPlugin)
             .add_plugin(PlayerPlugin)
+            .add_plugin(BotPlugin)
             .add_plugin(BoardPlugin)
+            .add_plugin(AnimationPlugin)
             .add_plugin(PigCollectionPlugin)
             .add_plugin(TurnPlugin)
             .add_plugin(PlayerCreationPlugin)
+            .add_plugin(IngameMenuPlugin)
+            .add_plugin(HandoverPlugin)
+            .add_plugin(HintsPlugin)
+            .add_plugin(UndoPlugin)
+            .add_plugin(ConnectionDialogPlugin)
             .add_plugin(NetworkingPlugin)
-            .add_plugin(DevPlugin);
+            .add_plugin(SavePlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(DevPlugin)
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(despawn_match));
+    }
+}
+
+/// Marks the top of everything a match spawns, which goes away with its children when the match ends.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Component)]
+struct MatchEntity;
+
+/// Everything visible during a match is spawned when it starts, so it can all go when it ends.
+fn despawn_match(mut commands: Commands, entities: Query<Entity, With<MatchEntity>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
     }
 }
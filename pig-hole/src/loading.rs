@@ -4,7 +4,9 @@ use bevy_asset_loader::{AssetCollection, AssetLoader};
 use bevy_kira_audio::AudioSource;
 
 mod board_assets;
+mod board_layout;
 pub use board_assets::BoardAssetCreator;
+pub use board_layout::{BoardLayout, BoardLayouts};
 
 pub struct LoadingPlugin;
 
@@ -13,10 +15,13 @@ pub struct LoadingPlugin;
 /// If interested, take a look at https://bevy-cheatbook.github.io/features/assets.html
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
+        app.add_asset::<BoardLayouts>()
+            .init_asset_loader::<board_layout::BoardLayoutsLoader>();
         AssetLoader::new(GameState::Loading)
             .with_collection::<FontAssets>()
             .with_collection::<AudioAssets>()
             .with_collection::<TextureAssets>()
+            .with_collection::<BoardLayoutAssets>()
             .continue_to_state(GameState::Menu)
             .build(app);
         app.insert_resource(BoardAssetCreator::default());
@@ -43,3 +48,9 @@ pub struct TextureAssets {
     #[asset(path = "textures/bevy.png")]
     pub texture_bevy: Handle<Image>,
 }
+
+#[derive(AssetCollection)]
+pub struct BoardLayoutAssets {
+    #[asset(path = "boards/layouts.boards.ron")]
+    pub layouts: Handle<BoardLayouts>,
+}
@@ -5,16 +5,8 @@ use bevy_prototype_lyon::{entity::ShapeBundle, prelude::*};
 pub struct BoardAssetCreator {}
 
 impl BoardAssetCreator {
-    pub fn get_trough_for_group(&self, group: u8) -> ShapeBundle {
-        match group {
-            1 => make_trough_bundle(Color::GRAY, Color::WHITE),
-            2 => make_trough_bundle(Color::DARK_GREEN, Color::GREEN),
-            3 => make_trough_bundle(Color::ORANGE, Color::YELLOW),
-            4 => make_trough_bundle(Color::BLUE, Color::AZURE),
-            5 => make_trough_bundle(Color::RED, Color::SALMON),
-            6 => make_trough_bundle(Color::BLACK, Color::GOLD),
-            group => panic!("Invalid group: {}", group),
-        }
+    pub fn get_trough(&self, fill_color: Color, outline_color: Color) -> ShapeBundle {
+        make_trough_bundle(fill_color, outline_color)
     }
 
     pub fn get_pig(&self) -> ShapeBundle {
@@ -33,18 +25,12 @@ impl BoardAssetCreator {
         make_highlight_bundle()
     }
 
-    pub fn get_border(&self) -> ShapeBundle {
-        let extents = self.get_board_extents();
-        let padding = self.get_board_padding();
-        make_border_bundle(extents, padding)
+    pub fn get_border(&self, extents: Vec2) -> ShapeBundle {
+        make_border_bundle(extents)
     }
 
-    pub fn get_board_extents(&self) -> Vec2 {
-        Vec2::new(150.0, 150.0)
-    }
-
-    pub fn get_board_padding(&self) -> f32 {
-        20.0
+    pub fn get_dice(&self) -> ShapeBundle {
+        make_dice_bundle()
     }
 }
 
@@ -101,23 +87,27 @@ fn make_highlight_bundle() -> ShapeBundle {
     )
 }
 
-fn make_border_bundle(extents: Vec2, padding: f32) -> ShapeBundle {
-    let mode = DrawMode::Stroke(StrokeMode::new(Color::BLACK, HOLE_LINE_WIDTH));
-    GeometryBuilder::new()
-        .add(&make_border_part_bundle(Vec2::new(
-            2. * extents.x + 3. * padding,
-            2. * extents.y + 3. * padding,
-        )))
-        .add(&make_border_part_bundle(Vec2::new(
-            2. * extents.x + 4. * padding,
-            2. * extents.y + 4. * padding,
-        )))
-        .build(mode, Transform::default())
+fn make_border_bundle(extents: Vec2) -> ShapeBundle {
+    GeometryBuilder::build_as(
+        &shapes::Rectangle {
+            extents,
+            ..default()
+        },
+        DrawMode::Stroke(StrokeMode::new(Color::BLACK, HOLE_LINE_WIDTH)),
+        Transform::default(),
+    )
 }
 
-fn make_border_part_bundle(extents: Vec2) -> impl Geometry {
-    shapes::Rectangle {
-        extents,
-        ..default()
-    }
+fn make_dice_bundle() -> ShapeBundle {
+    GeometryBuilder::build_as(
+        &shapes::Rectangle {
+            extents: Vec2::splat(50.0),
+            ..default()
+        },
+        DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::WHITE),
+            outline_mode: StrokeMode::new(Color::BLACK, HOLE_LINE_WIDTH),
+        },
+        Transform::default(),
+    )
 }
@@ -0,0 +1,159 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use rules::{Board, RuleSet};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use std::f32::consts::TAU;
+
+/// Space around every trough in a generated layout.
+const TROUGH_SPACING: f32 = 50.0;
+/// How far a generated layout may reach from the pig hole before it is scaled down.
+const MAX_BOARD_RADIUS: f32 = 200.0;
+
+/// Every board layout the game knows, in the order they are tried.
+#[derive(Debug, Clone, PartialEq, Deserialize, TypeUuid)]
+#[serde(transparent)]
+#[uuid = "8f3c2a6e-51d4-4b8a-9a0e-6c1f7d2b9e44"]
+pub struct BoardLayouts(pub Vec<BoardLayout>);
+
+/// Where the troughs of a board go and how they look.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BoardLayout {
+    pub name: String,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// Sizes of the rectangles drawn around the board, centered on the pig hole.
+    #[serde(default)]
+    pub borders: Vec<(f32, f32)>,
+    pub groups: Vec<GroupLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GroupLayout {
+    pub group: u8,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub fill: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub outline: Color,
+    /// One position per trough, the first one being trough 1 of the group.
+    pub troughs: Vec<(f32, f32)>,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// Colors are written as hex strings, e.g. `"FFD700"`.
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Color::hex(&hex).map_err(|_| D::Error::custom(format!("invalid color {}", hex)))
+}
+
+impl BoardLayout {
+    /// Whether the layout has exactly one position for every trough of the board.
+    pub fn fits(&self, board: &Board) -> bool {
+        self.groups.len() == board.groups().count()
+            && self
+                .groups
+                .iter()
+                .all(|layout| layout.troughs.len() == board.troughs_in_group(layout.group).count())
+    }
+
+    /// Places the groups in a circle around the pig hole, each with its troughs in a small circle.
+    /// Used for rules that no layout was made for.
+    pub fn generate(rules: &RuleSet) -> Self {
+        let pig_hole = rules.highest_roll();
+        let groups: Vec<u8> = rules
+            .board()
+            .groups()
+            .filter(|&group| group != pig_hole)
+            .collect();
+        let group_radius = |group: u8| match group {
+            1 => 0.0,
+            troughs => f32::from(troughs) * TROUGH_SPACING / TAU,
+        };
+        let largest_group_radius = groups.iter().copied().map(group_radius).fold(0.0, f32::max);
+        // Neighbouring groups must not overlap, even the largest ones
+        let ring_radius = (2.0 * largest_group_radius + TROUGH_SPACING)
+            / (2.0 * (TAU / 2.0 / groups.len().max(2) as f32).sin())
+            + TROUGH_SPACING;
+
+        let mut layouts = vec![GroupLayout {
+            group: pig_hole,
+            fill: Color::BLACK,
+            outline: Color::GOLD,
+            troughs: vec![(0.0, 0.0)],
+        }];
+        for (group_index, &group) in groups.iter().enumerate() {
+            let center = point_on_circle(group_index, groups.len(), ring_radius);
+            let (fill, outline) = PALETTE[group_index % PALETTE.len()];
+            layouts.push(GroupLayout {
+                group,
+                fill,
+                outline,
+                troughs: (0..usize::from(group))
+                    .map(|index| {
+                        let offset =
+                            point_on_circle(index, usize::from(group), group_radius(group));
+                        (center.0 + offset.0, center.1 + offset.1)
+                    })
+                    .collect(),
+            });
+        }
+        Self {
+            name: "Generated".to_string(),
+            scale: (MAX_BOARD_RADIUS / (ring_radius + largest_group_radius)).min(1.0),
+            borders: Vec::new(),
+            groups: layouts,
+        }
+    }
+}
+
+const PALETTE: [(Color, Color); 5] = [
+    (Color::GRAY, Color::WHITE),
+    (Color::DARK_GREEN, Color::GREEN),
+    (Color::ORANGE, Color::YELLOW),
+    (Color::BLUE, Color::AZURE),
+    (Color::RED, Color::SALMON),
+];
+
+/// The `index`th of `count` points spread evenly on a circle, starting at the top.
+fn point_on_circle(index: usize, count: usize, radius: f32) -> (f32, f32) {
+    let angle = TAU * index as f32 / count as f32;
+    (angle.sin() * radius, angle.cos() * radius)
+}
+
+impl BoardLayouts {
+    /// The first layout that fits the rules, or a generated one if none does.
+    pub fn for_rules(&self, rules: &RuleSet) -> BoardLayout {
+        let board = rules.board();
+        self.0
+            .iter()
+            .find(|layout| layout.fits(&board))
+            .cloned()
+            .unwrap_or_else(|| BoardLayout::generate(rules))
+    }
+}
+
+#[derive(Default)]
+pub struct BoardLayoutsLoader;
+
+impl AssetLoader for BoardLayoutsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let layouts: BoardLayouts = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(layouts));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["boards.ron"]
+    }
+}
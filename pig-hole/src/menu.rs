@@ -1,7 +1,10 @@
 use crate::GameState;
 
 use self::main_menu::MainMenuPlugin;
-use self::{browse_lobbies::BrowseLobbiesPlugin, create_lobby::CreateLobbyPlugin};
+use self::{
+    account::AccountPlugin, browse_lobbies::BrowseLobbiesPlugin, create_lobby::CreateLobbyPlugin,
+    local_game::LocalGamePlugin, replays::ReplaysPlugin,
+};
 use bevy::prelude::*;
 use bevy_egui::{
     egui::{self, FontId},
@@ -10,9 +13,13 @@ use bevy_egui::{
 use egui::FontFamily::*;
 use egui::TextStyle;
 
+mod account;
 mod browse_lobbies;
 mod create_lobby;
+mod local_game;
 mod main_menu;
+mod replays;
+mod rule_set_editor;
 mod state;
 use state::SubMenu;
 
@@ -23,7 +30,10 @@ impl Plugin for MenuPlugin {
         app.add_startup_system(configure_visuals);
         app.add_plugin(MainMenuPlugin)
             .add_plugin(CreateLobbyPlugin)
-            .add_plugin(BrowseLobbiesPlugin);
+            .add_plugin(BrowseLobbiesPlugin)
+            .add_plugin(LocalGamePlugin)
+            .add_plugin(ReplaysPlugin)
+            .add_plugin(AccountPlugin);
         app.add_system_set(SystemSet::on_exit(GameState::Menu).with_system(reset_menu));
         app.init_resource::<SubMenu>();
     }
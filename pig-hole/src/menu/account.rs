@@ -0,0 +1,192 @@
+use super::SubMenu;
+use crate::networking::{self, NetworkConfig, NetworkError};
+use crate::GameState;
+use bevy::{prelude::*, tasks::IoTaskPool};
+use bevy_egui::{egui, EguiContext};
+use matchmaker_models::client_api::{Credentials, Session};
+use std::sync::{Arc, RwLock};
+
+pub struct AccountPlugin;
+
+/// A session that is requested from the matchmaker in the background.
+type PendingSession = Arc<RwLock<Option<Result<Session, NetworkError>>>>;
+
+/// This plugin is responsible for logging in to the matchmaker and registering new accounts.
+/// Once logged in, the `Session` resource is inserted, which is needed to host and join lobbies.
+impl Plugin for AccountPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(show_menu)
+                .with_system(go_back)
+                .with_system(request_session)
+                .with_system(poll_session),
+        );
+        app.init_resource::<PendingSession>();
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub enum AccountSubMenu {
+    Main(ViewModel),
+}
+
+impl Default for AccountSubMenu {
+    fn default() -> Self {
+        AccountSubMenu::Main(default())
+    }
+}
+
+#[derive(Default, PartialEq, Clone)]
+pub struct ViewModel {
+    username: String,
+    password: String,
+    request: Option<AccountRequest>,
+    waiting: bool,
+    /// The last thing that went wrong, shown until the next attempt.
+    error: Option<NetworkError>,
+    back: bool,
+}
+
+impl ViewModel {
+    fn credentials(&self) -> Credentials {
+        Credentials {
+            username: self.username.trim().to_string(),
+            password: self.password.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AccountRequest {
+    LogIn,
+    Register,
+}
+
+fn go_back(mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Account(AccountSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.back {
+        *sub_menu = SubMenu::Main;
+    }
+}
+
+fn request_session(
+    mut sub_menu: ResMut<SubMenu>,
+    task_pool: Res<IoTaskPool>,
+    pending_session: Res<PendingSession>,
+    config: Res<NetworkConfig>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Account(AccountSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    let request = match view_model.request.take() {
+        Some(request) if !view_model.waiting => request,
+        _ => return,
+    };
+
+    let credentials = view_model.credentials();
+    let config = config.clone();
+    let inner_session = pending_session.clone();
+    task_pool
+        .spawn(async move {
+            let session = match request {
+                AccountRequest::LogIn => networking::log_in(&config, &credentials).await,
+                AccountRequest::Register => networking::register(&config, &credentials).await,
+            };
+            *inner_session.write().unwrap() = Some(session);
+        })
+        .detach();
+
+    view_model.waiting = true;
+    view_model.error = None;
+}
+
+fn poll_session(
+    mut commands: Commands,
+    pending_session: Res<PendingSession>,
+    mut sub_menu: ResMut<SubMenu>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Account(AccountSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if !view_model.waiting {
+        return;
+    }
+    let result = match pending_session.write().unwrap().take() {
+        Some(result) => result,
+        None => return,
+    };
+    view_model.waiting = false;
+    match result {
+        Ok(session) => {
+            info!("Logged in as {}", session.user.username);
+            commands.insert_resource(session);
+            *sub_menu = SubMenu::Main;
+        }
+        Err(error) => view_model.error = Some(error),
+    }
+}
+
+fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Account(AccountSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+
+    egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
+        let center = ui.available_size() / 2.0;
+        ui.allocate_ui_at_rect(
+            egui::Rect::from_center_size(center.to_pos2(), egui::Vec2::new(400.0, 400.0)),
+            |ui| {
+                ui.push_id("Account", |ui| {
+                    ui.heading("Account");
+                });
+                ui.add_space(100.0);
+                ui.horizontal(|ui| {
+                    ui.label("Username: ");
+                    ui.text_edit_singleline(&mut view_model.username);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Password: ");
+                    ui.add(egui::TextEdit::singleline(&mut view_model.password).password(true));
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Back").clicked() {
+                        view_model.back = true;
+                    }
+                    let enabled = !view_model.username.trim().is_empty()
+                        && !view_model.password.is_empty()
+                        && !view_model.waiting;
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Log in"))
+                        .clicked()
+                    {
+                        view_model.request = Some(AccountRequest::LogIn);
+                    }
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Register"))
+                        .clicked()
+                    {
+                        view_model.request = Some(AccountRequest::Register);
+                    }
+                });
+                if view_model.waiting {
+                    ui.add_space(100.0);
+                    ui.horizontal(|ui| {
+                        ui.label("Logging in...");
+                        ui.spinner();
+                    });
+                }
+                if let Some(error) = &view_model.error {
+                    ui.add_space(100.0);
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                }
+            },
+        );
+    });
+}
@@ -1,23 +1,42 @@
+use crate::networking::{self, ConnectionInfo, NetworkConfig, NetworkError, PendingClient};
 use crate::GameState;
-use bevy::prelude::*;
-use bevy_egui::{
-    egui::{self, RichText},
-    EguiContext,
-};
+use bevy::{prelude::*, tasks::IoTaskPool};
+use bevy_egui::{egui, EguiContext};
 
 mod waiting_for_players;
 use egui_extras::{self, Size, *};
+use matchmaker_models::client_api::{Lobby, Session};
+use std::sync::{Arc, RwLock};
 use waiting_for_players::{WaitingForPlayersPlugin, WaitingForPlayersSubMenu};
 
 use super::SubMenu;
 
+/// Seconds between two refreshes of the lobby list, if auto refresh is enabled.
+const AUTO_REFRESH_INTERVAL: f64 = 5.0;
+
 pub struct BrowseLobbiesPlugin;
 
+type FetchedLobbies = Arc<RwLock<Option<Result<Vec<Lobby>, NetworkError>>>>;
+/// The lobby an invite code leads to, once the matchmaker answered.
+type FoundInvite = Arc<RwLock<Option<Result<Lobby, NetworkError>>>>;
+
 /// This plugin is responsible for the game menu (containing only one button...)
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for BrowseLobbiesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_update(GameState::Menu).with_system(show_menu));
+        app.add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(show_menu)
+                .with_system(auto_refresh)
+                .with_system(fetch_lobbies)
+                .with_system(poll_lobbies)
+                .with_system(find_invite)
+                .with_system(poll_invite)
+                .with_system(join_lobby)
+                .with_system(poll_client_creation),
+        );
+        app.init_resource::<FetchedLobbies>();
+        app.init_resource::<FoundInvite>();
         app.add_plugin(WaitingForPlayersPlugin);
     }
 }
@@ -35,23 +54,252 @@ impl Default for BrowseLobbiesSubMenu {
 }
 #[derive(Clone, PartialEq, Default)]
 pub struct ViewModel {
-    player_name: String,
     back: bool,
     join_lobby: Option<String>,
-    player_name_empty_warning: bool,
+    /// The invite code the lobby in `join_lobby` was found with.
+    join_invite_code: Option<String>,
+    joining: bool,
+    /// Sent along when joining, lobbies with a password need it.
+    password: String,
+    /// Typed in by the player to join an unlisted lobby.
+    invite_code: String,
+    find_invite: Option<String>,
+    finding_invite: bool,
+    /// The last thing that went wrong, shown until the next attempt.
+    error: Option<NetworkError>,
+    name_filter: String,
+    auto_refresh: bool,
+    lobbies: Vec<Lobby>,
+    lobby_list_state: LobbyListState,
+    /// Time of the last completed fetch, in seconds since startup.
+    last_refresh: f64,
+}
+
+impl ViewModel {
+    fn connection_info(&self, lobby: String, session: &Session) -> ConnectionInfo {
+        ConnectionInfo {
+            lobby,
+            session: session.clone(),
+            password: Some(self.password.clone()).filter(|password| !password.is_empty()),
+            invite_code: self.join_invite_code.clone(),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Clone)]
+pub enum LobbyListState {
+    Idle,
+    Requested,
+    Fetching,
+}
+
+impl Default for LobbyListState {
+    fn default() -> Self {
+        // The list is fetched as soon as the menu is opened
+        Self::Requested
+    }
 }
 
-fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
+fn can_join(lobby: &Lobby) -> bool {
+    !lobby.playing && lobby.player_count < lobby.max_players
+}
+
+fn auto_refresh(time: Res<Time>, mut sub_menu: ResMut<SubMenu>) {
     let view_model = match &mut *sub_menu {
         SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)) => view_model,
         _ => return,
     };
-    if view_model.back {
-        *sub_menu = SubMenu::Main;
+    if view_model.auto_refresh
+        && view_model.lobby_list_state == LobbyListState::Idle
+        && time.seconds_since_startup() - view_model.last_refresh > AUTO_REFRESH_INTERVAL
+    {
+        view_model.lobby_list_state = LobbyListState::Requested;
+    }
+}
+
+fn fetch_lobbies(
+    mut sub_menu: ResMut<SubMenu>,
+    task_pool: Res<IoTaskPool>,
+    fetched_lobbies: Res<FetchedLobbies>,
+    config: Res<NetworkConfig>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.lobby_list_state != LobbyListState::Requested {
+        return;
+    }
+
+    let config = config.clone();
+    let inner_lobbies = fetched_lobbies.clone();
+    task_pool
+        .spawn(async move {
+            let lobbies = networking::list_lobbies(&config).await;
+            *inner_lobbies.write().unwrap() = Some(lobbies);
+        })
+        .detach();
+
+    view_model.lobby_list_state = LobbyListState::Fetching;
+}
+
+fn poll_lobbies(
+    time: Res<Time>,
+    fetched_lobbies: Res<FetchedLobbies>,
+    mut sub_menu: ResMut<SubMenu>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.lobby_list_state != LobbyListState::Fetching {
+        return;
+    }
+    let result = match fetched_lobbies.write().unwrap().take() {
+        Some(result) => result,
+        None => return,
+    };
+    match result {
+        Ok(mut lobbies) => {
+            lobbies.sort_by(|a, b| a.name.cmp(&b.name));
+            view_model.lobbies = lobbies;
+            view_model.error = None;
+        }
+        Err(error) => view_model.error = Some(error),
+    }
+    view_model.lobby_list_state = LobbyListState::Idle;
+    view_model.last_refresh = time.seconds_since_startup();
+}
+
+fn find_invite(
+    mut sub_menu: ResMut<SubMenu>,
+    task_pool: Res<IoTaskPool>,
+    found_invite: Res<FoundInvite>,
+    config: Res<NetworkConfig>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    let invite_code = match &view_model.find_invite {
+        Some(invite_code) if !view_model.finding_invite => invite_code.clone(),
+        _ => return,
+    };
+
+    let config = config.clone();
+    let inner_invite = found_invite.clone();
+    task_pool
+        .spawn(async move {
+            let lobby = networking::find_invite(&config, &invite_code).await;
+            *inner_invite.write().unwrap() = Some(lobby);
+        })
+        .detach();
+
+    view_model.finding_invite = true;
+    view_model.error = None;
+}
+
+/// Joins the lobby as soon as the invite code was found.
+fn poll_invite(found_invite: Res<FoundInvite>, mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if !view_model.finding_invite {
+        return;
+    }
+    let result = match found_invite.write().unwrap().take() {
+        Some(result) => result,
+        None => return,
+    };
+    let invite_code = view_model.find_invite.take();
+    view_model.finding_invite = false;
+    match result {
+        Ok(lobby) => {
+            view_model.join_lobby = Some(lobby.name);
+            view_model.join_invite_code = invite_code;
+        }
+        Err(error) => view_model.error = Some(error),
+    }
+}
+
+fn join_lobby(
+    mut sub_menu: ResMut<SubMenu>,
+    task_pool: Res<IoTaskPool>,
+    client: Res<PendingClient>,
+    config: Res<NetworkConfig>,
+    session: Option<Res<Session>>,
+) {
+    let (view_model, session) = match (&mut *sub_menu, session) {
+        (SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)), Some(session)) => {
+            (view_model, session)
+        }
+        _ => return,
+    };
+    let lobby_name = match &view_model.join_lobby {
+        Some(lobby_name) if !view_model.joining => lobby_name.clone(),
+        _ => return,
+    };
+
+    let info = view_model.connection_info(lobby_name, &session);
+    let config = config.clone();
+    let inner_client = client.clone();
+    task_pool
+        .spawn(async move {
+            let client = networking::join_lobby(&config, &info).await;
+            *inner_client.write().unwrap() = Some(client);
+        })
+        .detach();
+
+    view_model.joining = true;
+    view_model.error = None;
+}
+
+fn poll_client_creation(
+    mut commands: Commands,
+    client: Res<PendingClient>,
+    mut sub_menu: ResMut<SubMenu>,
+    session: Option<Res<Session>>,
+) {
+    let (view_model, session) = match (&mut *sub_menu, session) {
+        (SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)), Some(session)) => {
+            (view_model, session)
+        }
+        _ => return,
+    };
+    if !view_model.joining {
         return;
     }
-    if let Some(_lobby_name) = &view_model.join_lobby {
-        *sub_menu = SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::WaitingForPlayers(default()));
+    let result = match client.write().unwrap().take() {
+        Some(result) => result,
+        None => return,
+    };
+    let lobby_name = view_model.join_lobby.take().unwrap_or_default();
+    let info = view_model.connection_info(lobby_name.clone(), &session);
+    view_model.join_invite_code = None;
+    view_model.joining = false;
+    match result {
+        Ok(client) => {
+            networking::start_client(&mut commands, client, info);
+            *sub_menu = SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::WaitingForPlayers(
+                WaitingForPlayersSubMenu::new(lobby_name),
+            ));
+        }
+        Err(error) => view_model.error = Some(error),
+    }
+}
+
+fn show_menu(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut sub_menu: ResMut<SubMenu>,
+    session: Option<Res<Session>>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.back {
+        *sub_menu = SubMenu::Main;
         return;
     }
 
@@ -64,22 +312,55 @@ fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
                     ui.heading("Browsing Lobbies");
                 });
                 ui.add_space(100.0);
+                match &session {
+                    Some(session) => ui.label(format!("Playing as {}", session.user.username)),
+                    None => ui.colored_label(egui::Color32::RED, "Log in to join a lobby"),
+                };
+                ui.horizontal(|ui| {
+                    ui.label("Password: ");
+                    ui.add(egui::TextEdit::singleline(&mut view_model.password).password(true))
+                        .on_hover_text("Only needed for lobbies with a password");
+                });
                 ui.horizontal(|ui| {
-                    ui.label("Player Name: ");
-                    ui.text_edit_singleline(&mut view_model.player_name);
-                    if !view_model.player_name.is_empty() {
-                        view_model.player_name_empty_warning = false;
+                    ui.label("Invite code: ");
+                    ui.text_edit_singleline(&mut view_model.invite_code);
+                    let enabled = session.is_some()
+                        && !view_model.invite_code.is_empty()
+                        && !view_model.joining
+                        && !view_model.finding_invite;
+                    if ui.add_enabled(enabled, egui::Button::new("Join")).clicked() {
+                        view_model.find_invite = Some(view_model.invite_code.trim().to_string());
                     }
-                    if view_model.player_name_empty_warning {
-                        ui.add(egui::Label::new(
-                            RichText::new("*").color(egui::Color32::RED),
-                        ));
-                    };
                 });
+                ui.horizontal(|ui| {
+                    ui.label("Search: ");
+                    ui.text_edit_singleline(&mut view_model.name_filter);
+                });
+                ui.horizontal(|ui| {
+                    let is_idle = view_model.lobby_list_state == LobbyListState::Idle;
+                    if ui
+                        .add_enabled(is_idle, egui::Button::new("Refresh"))
+                        .clicked()
+                    {
+                        view_model.lobby_list_state = LobbyListState::Requested;
+                    }
+                    ui.checkbox(&mut view_model.auto_refresh, "Auto refresh");
+                    if !is_idle {
+                        ui.spinner();
+                    }
+                });
+                let filter = view_model.name_filter.to_lowercase();
+                let lobbies: Vec<Lobby> = view_model
+                    .lobbies
+                    .iter()
+                    .filter(|lobby| lobby.name.to_lowercase().contains(&filter))
+                    .cloned()
+                    .collect();
                 TableBuilder::new(ui)
                     .striped(true)
                     .column(Size::remainder().at_least(80.0))
                     .column(Size::initial(50.0))
+                    .column(Size::initial(80.0))
                     .column(Size::initial(50.0))
                     .header(40.0, |mut header| {
                         header.col(|ui| {
@@ -88,32 +369,54 @@ fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
                         header.col(|ui| {
                             ui.label("Players");
                         });
+                        header.col(|ui| {
+                            ui.label("Status");
+                        });
                     })
                     .body(|mut body| {
-                        for _ in 0..100 {
+                        for lobby in lobbies.iter() {
                             body.row(30.0, |mut row| {
-                                let lobby_name = "Lobby Name";
                                 row.col(|ui| {
-                                    ui.small(lobby_name);
+                                    if lobby.password_protected {
+                                        ui.small(format!("{} (password)", lobby.name));
+                                    } else {
+                                        ui.small(lobby.name.as_str());
+                                    }
                                 });
                                 row.col(|ui| {
-                                    ui.label("0/8");
+                                    ui.label(format!(
+                                        "{}/{}",
+                                        lobby.player_count, lobby.max_players
+                                    ));
                                 });
                                 row.col(|ui| {
-                                    if ui
-                                        .add(egui::Button::new(egui::RichText::new("Join").small()))
-                                        .clicked()
-                                    {
-                                        view_model.player_name_empty_warning =
-                                            view_model.player_name.is_empty();
-                                        if !view_model.player_name_empty_warning {
-                                            view_model.join_lobby = Some(lobby_name.to_string());
-                                        }
+                                    ui.small(if lobby.playing { "Playing" } else { "Waiting" });
+                                });
+                                row.col(|ui| {
+                                    let join_button =
+                                        egui::Button::new(egui::RichText::new("Join").small());
+                                    let enabled =
+                                        session.is_some() && can_join(lobby) && !view_model.joining;
+                                    if ui.add_enabled(enabled, join_button).clicked() {
+                                        view_model.join_lobby = Some(lobby.name.clone());
+                                        view_model.join_invite_code = None;
                                     };
                                 });
                             });
                         }
                     });
+                if lobbies.is_empty() && view_model.lobby_list_state == LobbyListState::Idle {
+                    ui.label("No lobbies found");
+                }
+                if view_model.joining || view_model.finding_invite {
+                    ui.horizontal(|ui| {
+                        ui.label("Joining Lobby...");
+                        ui.spinner();
+                    });
+                }
+                if let Some(error) = &view_model.error {
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                }
                 ui.add_space(20.0);
                 ui.horizontal(|ui| {
                     if ui.button("Back").clicked() {
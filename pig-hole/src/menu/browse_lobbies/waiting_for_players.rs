@@ -1,9 +1,22 @@
+use super::BrowseLobbiesSubMenu;
+use crate::menu::SubMenu;
+use crate::networking::{self, ConnectionLost, Lobby, ReconnectEvent, Reconnecting};
+use crate::GameState;
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use renet::RenetClient;
 
 pub struct WaitingForPlayersPlugin;
 
+/// This plugin shows the players in a joined lobby until the host starts the match.
 impl Plugin for WaitingForPlayersPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(show_menu)
+                .with_system(cancel),
+        );
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -11,6 +24,15 @@ pub enum WaitingForPlayersSubMenu {
     Main(ViewModel),
 }
 
+impl WaitingForPlayersSubMenu {
+    pub fn new(lobby_name: String) -> Self {
+        WaitingForPlayersSubMenu::Main(ViewModel {
+            lobby_name,
+            ..default()
+        })
+    }
+}
+
 impl Default for WaitingForPlayersSubMenu {
     fn default() -> Self {
         WaitingForPlayersSubMenu::Main(default())
@@ -18,4 +40,96 @@ impl Default for WaitingForPlayersSubMenu {
 }
 
 #[derive(Clone, PartialEq, Default)]
-pub struct ViewModel {}
+pub struct ViewModel {
+    lobby_name: String,
+    cancel: bool,
+}
+
+fn cancel(
+    mut commands: Commands,
+    mut sub_menu: ResMut<SubMenu>,
+    client: Option<ResMut<RenetClient>>,
+) {
+    match &*sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::WaitingForPlayers(
+            WaitingForPlayersSubMenu::Main(view_model),
+        )) if view_model.cancel => (),
+        _ => return,
+    };
+    if let Some(mut client) = client {
+        client.disconnect();
+    }
+    networking::go_offline(&mut commands);
+    *sub_menu = SubMenu::BrowseLobbies(default());
+}
+
+fn show_menu(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut sub_menu: ResMut<SubMenu>,
+    mut reconnect_events: EventWriter<ReconnectEvent>,
+    lobby: Res<Lobby>,
+    client: Option<Res<RenetClient>>,
+    connection_lost: Option<Res<ConnectionLost>>,
+    reconnecting: Option<Res<Reconnecting>>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::BrowseLobbies(BrowseLobbiesSubMenu::WaitingForPlayers(
+            WaitingForPlayersSubMenu::Main(view_model),
+        )) => view_model,
+        _ => return,
+    };
+
+    egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
+        let center = ui.available_size() / 2.0;
+        ui.allocate_ui_at_rect(
+            egui::Rect::from_center_size(center.to_pos2(), egui::Vec2::new(400.0, 400.0)),
+            |ui| {
+                ui.push_id("Waiting for Players", |ui| {
+                    ui.heading(view_model.lobby_name.as_str());
+                });
+                ui.add_space(50.0);
+                match &client {
+                    _ if reconnecting.is_some() => {
+                        ui.horizontal(|ui| {
+                            ui.label("Reconnecting...");
+                            ui.spinner();
+                        });
+                    }
+                    _ if connection_lost.is_some() => {
+                        if let Some(ConnectionLost(error)) = connection_lost.as_deref() {
+                            ui.colored_label(egui::Color32::RED, error.to_string());
+                        }
+                        if ui.button("Retry").clicked() {
+                            reconnect_events.send(ReconnectEvent);
+                        }
+                    }
+                    Some(client) if client.is_connected() => {
+                        let own_id = client.client_id();
+                        for player in lobby.players.iter() {
+                            if player.id == own_id {
+                                ui.label(format!("{} (you)", player.username));
+                            } else {
+                                ui.label(player.username.as_str());
+                            }
+                        }
+                        ui.add_space(20.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Waiting for the host to start the match...");
+                            ui.spinner();
+                        });
+                    }
+                    _ => {
+                        ui.horizontal(|ui| {
+                            ui.label("Connecting...");
+                            ui.spinner();
+                        });
+                    }
+                }
+                ui.add_space(20.0);
+                if ui.button("Cancel").clicked() {
+                    view_model.cancel = true;
+                }
+            },
+        );
+    });
+}
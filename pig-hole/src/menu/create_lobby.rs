@@ -1,18 +1,15 @@
-use std::sync::{Arc, RwLock};
-
 use super::SubMenu;
-use crate::{networking, GameState};
+use crate::networking::{self, HostInfo, HostedMatch, NetworkConfig, NetworkError, PendingLobby};
+use crate::GameState;
 use bevy::{prelude::*, tasks::IoTaskPool};
 use bevy_egui::{egui, EguiContext};
-use renet::RenetClient;
+use matchmaker_models::client_api::Session;
 use waiting_for_players::{WaitingForPlayersPlugin, WaitingForPlayersSubMenu};
 
 mod waiting_for_players;
 
 pub struct CreateLobbyPlugin;
 
-type Client = Arc<RwLock<Option<RenetClient>>>;
-
 /// This plugin is responsible for the game menu (containing only one button...)
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for CreateLobbyPlugin {
@@ -22,9 +19,9 @@ impl Plugin for CreateLobbyPlugin {
                 .with_system(show_menu)
                 .with_system(go_back)
                 .with_system(create_lobby)
-                .with_system(poll_client_creation),
+                .with_system(poll_lobby_creation),
         );
-        app.init_resource::<Client>();
+        app.init_resource::<PendingLobby>();
         app.add_plugin(WaitingForPlayersPlugin);
     }
 }
@@ -40,19 +37,52 @@ impl Default for CreateLobbySubMenu {
         CreateLobbySubMenu::Main(default())
     }
 }
+
+impl CreateLobbySubMenu {
+    /// Hosts a saved match again, in a lobby named like the old one.
+    pub fn resume(hosted_match: HostedMatch) -> Self {
+        CreateLobbySubMenu::Main(ViewModel {
+            lobby_name: hosted_match.host.lobby.clone(),
+            dedicated: hosted_match.host.dedicated,
+            password: hosted_match.host.password.clone().unwrap_or_default(),
+            unlisted: hosted_match.host.unlisted,
+            resumed_match: Some(hosted_match),
+            ..default()
+        })
+    }
+}
+
 #[derive(Default, PartialEq, Clone)]
 pub struct ViewModel {
-    player_name: String,
     lobby_name: String,
+    /// Host the lobby without playing in it.
+    dedicated: bool,
+    /// Players have to know it to join, unless empty.
+    password: String,
+    unlisted: bool,
+    /// The saved match to continue instead of starting a new one.
+    resumed_match: Option<HostedMatch>,
     back: bool,
     lobby_creation_state: LobbyCreationState,
 }
 
+impl ViewModel {
+    fn host_info(&self) -> HostInfo {
+        HostInfo {
+            lobby: self.lobby_name.clone(),
+            dedicated: self.dedicated,
+            password: Some(self.password.clone()).filter(|password| !password.is_empty()),
+            unlisted: self.unlisted,
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Clone)]
 pub enum LobbyCreationState {
     None,
     Requested,
     Creating,
+    Failed(NetworkError),
 }
 
 impl Default for LobbyCreationState {
@@ -61,9 +91,9 @@ impl Default for LobbyCreationState {
     }
 }
 
-fn poll_client_creation(
+fn poll_lobby_creation(
     mut commands: Commands,
-    client: ResMut<Client>,
+    pending_lobby: Res<PendingLobby>,
     mut sub_menu: ResMut<SubMenu>,
 ) {
     let view_model = match &mut *sub_menu {
@@ -74,9 +104,27 @@ fn poll_client_creation(
         view_model.lobby_creation_state,
         LobbyCreationState::Creating
     ) {
-        let client = client.write().unwrap().take();
-        if let Some(client) = client {
-            commands.insert_resource(client)
+        let lobby = pending_lobby.write().unwrap().take();
+        match lobby {
+            Some(Ok(lobby)) => {
+                let host = view_model.host_info();
+                let lobby_name = host.lobby.clone();
+                let invite_code = lobby.invite_code().map(str::to_string);
+                match view_model.resumed_match.take() {
+                    Some(mut hosted_match) => {
+                        hosted_match.host = host;
+                        networking::resume_hosting(&mut commands, lobby, hosted_match);
+                    }
+                    None => networking::start_hosting(&mut commands, lobby, host),
+                }
+                *sub_menu = SubMenu::CreateLobby(CreateLobbySubMenu::WaitingForPlayers(
+                    WaitingForPlayersSubMenu::new(lobby_name, invite_code),
+                ));
+            }
+            Some(Err(error)) => {
+                view_model.lobby_creation_state = LobbyCreationState::Failed(error);
+            }
+            None => (),
         }
     }
 }
@@ -91,9 +139,17 @@ fn go_back(mut sub_menu: ResMut<SubMenu>) {
     }
 }
 
-fn create_lobby(mut sub_menu: ResMut<SubMenu>, task_pool: Res<IoTaskPool>, client: Res<Client>) {
-    let view_model = match &mut *sub_menu {
-        SubMenu::CreateLobby(CreateLobbySubMenu::Main(view_model)) => view_model,
+fn create_lobby(
+    mut sub_menu: ResMut<SubMenu>,
+    task_pool: Res<IoTaskPool>,
+    pending_lobby: Res<PendingLobby>,
+    config: Res<NetworkConfig>,
+    session: Option<Res<Session>>,
+) {
+    let (view_model, session) = match (&mut *sub_menu, session) {
+        (SubMenu::CreateLobby(CreateLobbySubMenu::Main(view_model)), Some(session)) => {
+            (view_model, session.clone())
+        }
         _ => return,
     };
     if !matches!(
@@ -103,25 +159,25 @@ fn create_lobby(mut sub_menu: ResMut<SubMenu>, task_pool: Res<IoTaskPool>, clien
         return;
     }
 
-    let username = view_model.player_name.clone();
-    let lobby_name = view_model.lobby_name.clone();
+    let host = view_model.host_info();
+    let config = config.clone();
     // Source: https://github.com/vleue/jornet/blob/2a414a8f85f975ae8d54b9e3ceab348db7c6250d/bevy-jornet/src/leaderboards.rs#L49-L55
-    let inner_client = client.clone();
+    let inner_lobby = pending_lobby.clone();
     task_pool
         .spawn(async move {
-            let client = networking::create_lobby(&username, &lobby_name).await;
-            *inner_client.write().unwrap() = Some(client);
+            let lobby = networking::host_lobby(&config, &session, &host).await;
+            *inner_lobby.write().unwrap() = Some(lobby);
         })
         .detach();
 
     view_model.lobby_creation_state = LobbyCreationState::Creating;
 }
 
-fn clean_up(mut commands: Commands) {
-    commands.remove_resource::<RenetClient>();
-}
-
-fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
+fn show_menu(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut sub_menu: ResMut<SubMenu>,
+    session: Option<Res<Session>>,
+) {
     let view_model = match &mut *sub_menu {
         SubMenu::CreateLobby(CreateLobbySubMenu::Main(view_model)) => view_model,
         _ => return,
@@ -136,21 +192,39 @@ fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
                     ui.heading("Creating Server");
                 });
                 ui.add_space(100.0);
-                ui.horizontal(|ui| {
-                    ui.label("Player Name: ");
-                    ui.text_edit_singleline(&mut view_model.player_name);
-                });
+                let resuming = view_model.resumed_match.is_some();
+                if resuming {
+                    ui.label("The players get their seats back by joining with their accounts");
+                }
+                match &session {
+                    Some(session) => ui.label(format!("Hosting as {}", session.user.username)),
+                    None => ui.colored_label(egui::Color32::RED, "Log in to host a lobby"),
+                };
                 ui.horizontal(|ui| {
                     ui.label("Lobby Name: ");
                     ui.text_edit_singleline(&mut view_model.lobby_name);
                 });
+                ui.horizontal(|ui| {
+                    ui.label("Password: ");
+                    ui.add(egui::TextEdit::singleline(&mut view_model.password).password(true))
+                        .on_hover_text("Leave empty to let anybody join");
+                });
+                ui.checkbox(&mut view_model.unlisted, "Unlisted")
+                    .on_hover_text("Only players with the invite code can join");
+                ui.add_enabled(
+                    !resuming,
+                    egui::Checkbox::new(&mut view_model.dedicated, "Dedicated server"),
+                );
                 ui.horizontal(|ui| {
                     if ui.button("Back").clicked() {
                         view_model.back = true;
                     }
-                    let enabled = !view_model.player_name.is_empty()
+                    let enabled = session.is_some()
                         && !view_model.lobby_name.is_empty()
-                        && matches!(view_model.lobby_creation_state, LobbyCreationState::None);
+                        && matches!(
+                            view_model.lobby_creation_state,
+                            LobbyCreationState::None | LobbyCreationState::Failed(_)
+                        );
                     if ui
                         .add_enabled(enabled, egui::Button::new("Create Lobby"))
                         .clicked()
@@ -158,12 +232,19 @@ fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
                         view_model.lobby_creation_state = LobbyCreationState::Requested;
                     }
                 });
-                if !matches!(view_model.lobby_creation_state, LobbyCreationState::None) {
-                    ui.add_space(100.0);
-                    ui.horizontal(|ui| {
-                        ui.label("Creating Lobby...");
-                        ui.spinner();
-                    });
+                match &view_model.lobby_creation_state {
+                    LobbyCreationState::None => (),
+                    LobbyCreationState::Failed(error) => {
+                        ui.add_space(100.0);
+                        ui.colored_label(egui::Color32::RED, error.to_string());
+                    }
+                    _ => {
+                        ui.add_space(100.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Creating Lobby...");
+                            ui.spinner();
+                        });
+                    }
                 }
             },
         );
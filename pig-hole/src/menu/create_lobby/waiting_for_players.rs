@@ -1,9 +1,26 @@
+use super::CreateLobbySubMenu;
+use crate::menu::rule_set_editor::show_rule_set_editor;
+use crate::menu::SubMenu;
+use crate::networking::{
+    self, ConnectionLost, KickPlayerEvent, LobbySettings, ServerLobby, StartMatchEvent, TurnClock,
+    MAX_PLAYERS,
+};
+use crate::GameState;
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use renet::RenetClient;
 
 pub struct WaitingForPlayersPlugin;
 
+/// This plugin shows the host who joined the lobby and lets them start the match.
 impl Plugin for WaitingForPlayersPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(show_menu)
+                .with_system(close_lobby),
+        );
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -11,6 +28,16 @@ pub enum WaitingForPlayersSubMenu {
     Main(ViewModel),
 }
 
+impl WaitingForPlayersSubMenu {
+    pub fn new(lobby_name: String, invite_code: Option<String>) -> Self {
+        WaitingForPlayersSubMenu::Main(ViewModel {
+            lobby_name,
+            invite_code,
+            ..default()
+        })
+    }
+}
+
 impl Default for WaitingForPlayersSubMenu {
     fn default() -> Self {
         WaitingForPlayersSubMenu::Main(default())
@@ -18,4 +45,138 @@ impl Default for WaitingForPlayersSubMenu {
 }
 
 #[derive(Clone, PartialEq, Default)]
-pub struct ViewModel {}
+pub struct ViewModel {
+    lobby_name: String,
+    /// Shown to the host, so they can pass it on to the players of an unlisted lobby.
+    invite_code: Option<String>,
+    /// Whether the match has been requested, so it is not started twice.
+    starting: bool,
+    close: bool,
+}
+
+fn close_lobby(mut commands: Commands, mut sub_menu: ResMut<SubMenu>) {
+    match &*sub_menu {
+        SubMenu::CreateLobby(CreateLobbySubMenu::WaitingForPlayers(
+            WaitingForPlayersSubMenu::Main(view_model),
+        )) if view_model.close => (),
+        _ => return,
+    };
+    networking::go_offline(&mut commands);
+    *sub_menu = SubMenu::CreateLobby(default());
+}
+
+fn show_menu(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut sub_menu: ResMut<SubMenu>,
+    mut settings: ResMut<LobbySettings>,
+    mut kick_player_events: EventWriter<KickPlayerEvent>,
+    mut start_match_events: EventWriter<StartMatchEvent>,
+    lobby: Option<Res<ServerLobby>>,
+    client: Option<Res<RenetClient>>,
+    connection_lost: Option<Res<ConnectionLost>>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::CreateLobby(CreateLobbySubMenu::WaitingForPlayers(
+            WaitingForPlayersSubMenu::Main(view_model),
+        )) => view_model,
+        _ => return,
+    };
+    let lobby = match lobby {
+        Some(lobby) => lobby,
+        None => return,
+    };
+    let own_id = client.map(|client| client.client_id());
+
+    egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
+        let center = ui.available_size() / 2.0;
+        ui.allocate_ui_at_rect(
+            egui::Rect::from_center_size(center.to_pos2(), egui::Vec2::new(400.0, 500.0)),
+            |ui| {
+                ui.push_id("Waiting for Players", |ui| {
+                    ui.heading(view_model.lobby_name.as_str());
+                });
+                if let Some(invite_code) = &view_model.invite_code {
+                    ui.horizontal(|ui| {
+                        ui.label("Invite code: ");
+                        ui.monospace(invite_code.as_str());
+                        if ui.small_button("Copy").clicked() {
+                            ui.output().copied_text = invite_code.clone();
+                        }
+                    });
+                }
+                ui.add_space(50.0);
+                for player in lobby.players().iter() {
+                    ui.horizontal(|ui| {
+                        if Some(player.id) == own_id {
+                            ui.label(format!("{} (you)", player.username));
+                        } else {
+                            ui.label(player.username.as_str());
+                            if ui.small_button("Kick").clicked() {
+                                kick_player_events.send(KickPlayerEvent(player.id));
+                            }
+                        }
+                    });
+                }
+                ui.add_space(20.0);
+                let max_players = settings.max_players;
+                ui.add(
+                    egui::Slider::new(&mut settings.min_players, 1..=max_players)
+                        .text("Min players"),
+                );
+                let min_players = settings.min_players;
+                ui.add(
+                    egui::Slider::new(&mut settings.max_players, min_players..=MAX_PLAYERS)
+                        .text("Max players"),
+                );
+                if !lobby.is_playing() {
+                    show_turn_clock_editor(ui, &mut settings.turn_clock);
+                    show_rule_set_editor(ui, &mut settings.rules);
+                }
+                ui.add_space(20.0);
+                let player_count = lobby.players().len();
+                if lobby.is_playing() {
+                    ui.label("The match is running");
+                } else if settings.allows_player_count(player_count) {
+                    if ui
+                        .add_enabled(!view_model.starting, egui::Button::new("Start Match"))
+                        .clicked()
+                    {
+                        start_match_events.send(StartMatchEvent);
+                        view_model.starting = true;
+                    }
+                } else {
+                    ui.label(format!(
+                        "Waiting for {} to {} players...",
+                        settings.min_players, settings.max_players
+                    ));
+                }
+                if let Some(ConnectionLost(error)) = connection_lost.as_deref() {
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                }
+                ui.add_space(20.0);
+                if ui.button("Close Lobby").clicked() {
+                    view_model.close = true;
+                }
+            },
+        );
+    });
+}
+
+fn show_turn_clock_editor(ui: &mut egui::Ui, turn_clock: &mut Option<TurnClock>) {
+    let mut enabled = turn_clock.is_some();
+    ui.checkbox(&mut enabled, "Turn clock");
+    if !enabled {
+        *turn_clock = None;
+        return;
+    }
+    let turn_clock = turn_clock.get_or_insert_with(TurnClock::default);
+    ui.add(
+        egui::Slider::new(&mut turn_clock.seconds, 10..=300)
+            .suffix("s")
+            .text("Per turn"),
+    );
+    ui.add(
+        egui::Slider::new(&mut turn_clock.timeouts_until_afk, 1..=5)
+            .text("Timeouts until a bot takes over"),
+    );
+}
@@ -0,0 +1,209 @@
+use super::rule_set_editor::show_rule_set_editor;
+use super::SubMenu;
+use crate::player_creation::{Controller, Seat, Seats};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use rules::bot::Difficulty;
+use rules::{RuleSet, UndoPolicy};
+
+pub struct LocalGamePlugin;
+
+const MIN_PLAYERS: usize = 2;
+const MAX_PLAYERS: usize = 8;
+
+/// This plugin is responsible for setting up a hot-seat game where all players share one screen,
+/// optionally against computer opponents
+impl Plugin for LocalGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(show_menu)
+                .with_system(go_back)
+                .with_system(start_game),
+        );
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub enum LocalGameSubMenu {
+    Main(ViewModel),
+}
+
+impl Default for LocalGameSubMenu {
+    fn default() -> Self {
+        LocalGameSubMenu::Main(default())
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub struct ViewModel {
+    seats: Vec<Seat>,
+    rules: RuleSet,
+    undo_policy: UndoPolicy,
+    back: bool,
+    start: bool,
+}
+
+impl Default for ViewModel {
+    fn default() -> Self {
+        Self {
+            seats: (1..=MIN_PLAYERS).map(get_default_seat).collect(),
+            rules: RuleSet::default(),
+            undo_policy: UndoPolicy::default(),
+            back: false,
+            start: false,
+        }
+    }
+}
+
+fn get_default_seat(number: usize) -> Seat {
+    Seat {
+        name: format!("Player {}", number),
+        controller: Controller::Local,
+    }
+}
+
+fn get_controller_label(controller: Controller) -> String {
+    match controller {
+        Controller::Bot(difficulty) => format!("Bot ({})", difficulty),
+        _ => "Human".to_string(),
+    }
+}
+
+fn go_back(mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::LocalGame(LocalGameSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.back {
+        *sub_menu = SubMenu::Main;
+    }
+}
+
+fn start_game(
+    mut commands: Commands,
+    mut sub_menu: ResMut<SubMenu>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::LocalGame(LocalGameSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if !view_model.start {
+        return;
+    }
+    view_model.start = false;
+
+    let seats = view_model
+        .seats
+        .iter()
+        .map(|seat| Seat {
+            name: seat.name.trim().to_string(),
+            controller: seat.controller,
+        })
+        .collect();
+    commands.insert_resource(Seats(seats));
+    commands.insert_resource(view_model.rules.clone());
+    commands.insert_resource(view_model.undo_policy);
+    state
+        .set(GameState::Playing)
+        .expect("Failed to start local game");
+}
+
+fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::LocalGame(LocalGameSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+
+    egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
+        let center = ui.available_size() / 2.0;
+        ui.allocate_ui_at_rect(
+            egui::Rect::from_center_size(center.to_pos2(), egui::Vec2::new(400.0, 600.0)),
+            |ui| {
+                ui.push_id("Local Game", |ui| {
+                    ui.heading("Local Game");
+                });
+                ui.add_space(50.0);
+                let mut removed_player = None;
+                let player_count = view_model.seats.len();
+                for (index, seat) in view_model.seats.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}. ", index + 1));
+                        ui.text_edit_singleline(&mut seat.name);
+                        egui::ComboBox::from_id_source(index)
+                            .selected_text(get_controller_label(seat.controller))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut seat.controller,
+                                    Controller::Local,
+                                    get_controller_label(Controller::Local),
+                                );
+                                for difficulty in Difficulty::ALL {
+                                    let controller = Controller::Bot(difficulty);
+                                    ui.selectable_value(
+                                        &mut seat.controller,
+                                        controller,
+                                        get_controller_label(controller),
+                                    );
+                                }
+                            });
+                        if ui
+                            .add_enabled(player_count > MIN_PLAYERS, egui::Button::new("-"))
+                            .clicked()
+                        {
+                            removed_player = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed_player {
+                    view_model.seats.remove(index);
+                }
+                if ui
+                    .add_enabled(
+                        view_model.seats.len() < MAX_PLAYERS,
+                        egui::Button::new("Add Player"),
+                    )
+                    .clicked()
+                {
+                    let number = view_model.seats.len() + 1;
+                    view_model.seats.push(get_default_seat(number));
+                }
+                ui.add_space(20.0);
+                ui.horizontal(|ui| {
+                    ui.label("Undo: ");
+                    egui::ComboBox::from_id_source("Undo")
+                        .selected_text(view_model.undo_policy.to_string())
+                        .show_ui(ui, |ui| {
+                            for policy in UndoPolicy::ALL {
+                                ui.selectable_value(
+                                    &mut view_model.undo_policy,
+                                    policy,
+                                    policy.to_string(),
+                                );
+                            }
+                        });
+                });
+                ui.add_space(20.0);
+                show_rule_set_editor(ui, &mut view_model.rules);
+                ui.add_space(20.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Back").clicked() {
+                        view_model.back = true;
+                    }
+                    let enabled = view_model
+                        .seats
+                        .iter()
+                        .all(|seat| !seat.name.trim().is_empty());
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Start Game"))
+                        .clicked()
+                    {
+                        view_model.start = true;
+                    }
+                });
+            },
+        );
+    });
+}
@@ -1,7 +1,11 @@
+use crate::player_creation::{ResumedGame, Seats};
+use crate::save::{SavedGame, SavedMatch};
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
+use matchmaker_models::client_api::Session;
 
+use super::create_lobby::CreateLobbySubMenu;
 use super::SubMenu;
 
 pub struct MainMenuPlugin;
@@ -14,10 +18,21 @@ impl Plugin for MainMenuPlugin {
     }
 }
 
-fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
+fn show_menu(
+    mut commands: Commands,
+    mut egui_ctx: ResMut<EguiContext>,
+    mut sub_menu: ResMut<SubMenu>,
+    mut state: ResMut<State<GameState>>,
+    saved_game: Option<Res<SavedGame>>,
+    session: Option<Res<Session>>,
+    mut continue_error: Local<Option<String>>,
+) {
     if !matches!(*sub_menu, SubMenu::Main) {
         return;
     }
+    let saved_game = saved_game
+        .as_deref()
+        .and_then(|saved_game| saved_game.0.as_ref());
 
     egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
         ui.vertical_centered(|ui| {
@@ -28,13 +43,79 @@ fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
             ui.add_space(100.0);
             let layout = egui::Layout::centered_and_justified(ui.layout().main_dir());
             ui.allocate_ui_with_layout(egui::Vec2::new(300.0, 0.0), layout, |ui| {
-                if ui.button("Browse Games").clicked() {
+                let saved_match = match saved_game {
+                    Some(Ok(save_file)) => Some(&save_file.saved_match),
+                    _ => None,
+                };
+                if ui
+                    .add_enabled(saved_match.is_some(), egui::Button::new("Continue"))
+                    .clicked()
+                {
+                    match saved_match.cloned() {
+                        Some(SavedMatch::Local {
+                            seats,
+                            game,
+                            undo_policy,
+                        }) => match state.set(GameState::Playing) {
+                            Ok(()) => {
+                                commands.insert_resource(Seats(seats));
+                                commands.insert_resource(game.rules().clone());
+                                commands.insert_resource(undo_policy);
+                                commands.insert_resource(ResumedGame(game));
+                                *continue_error = None;
+                            }
+                            Err(error) => {
+                                *continue_error =
+                                    Some(format!("Failed to continue the saved game: {:?}", error))
+                            }
+                        },
+                        Some(SavedMatch::Hosted(hosted_match)) => {
+                            *sub_menu =
+                                SubMenu::CreateLobby(CreateLobbySubMenu::resume(hosted_match))
+                        }
+                        None => (),
+                    }
+                }
+                if ui.button("Local Game").clicked() {
+                    *sub_menu = SubMenu::LocalGame(default())
+                }
+                // Lobbies can only be hosted and joined with an account
+                let logged_in = session.is_some();
+                if ui
+                    .add_enabled(logged_in, egui::Button::new("Browse Games"))
+                    .clicked()
+                {
                     *sub_menu = SubMenu::BrowseLobbies(default())
                 }
-                if ui.button("Host Game").clicked() {
+                if ui
+                    .add_enabled(logged_in, egui::Button::new("Host Game"))
+                    .clicked()
+                {
                     *sub_menu = SubMenu::CreateLobby(default())
                 }
+                if ui.button("Replays").clicked() {
+                    *sub_menu = SubMenu::Replays(default())
+                }
+                if logged_in {
+                    if ui.button("Log out").clicked() {
+                        commands.remove_resource::<Session>();
+                    }
+                } else if ui.button("Log in").clicked() {
+                    *sub_menu = SubMenu::Account(default())
+                }
             });
+            if let Some(session) = &session {
+                ui.add_space(20.0);
+                ui.label(format!("Logged in as {}", session.user.username));
+            }
+            if let Some(Err(error)) = saved_game {
+                ui.add_space(20.0);
+                ui.colored_label(egui::Color32::RED, error.to_string());
+            }
+            if let Some(error) = &*continue_error {
+                ui.add_space(20.0);
+                ui.colored_label(egui::Color32::RED, error);
+            }
         });
     });
 }
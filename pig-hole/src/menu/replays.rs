@@ -0,0 +1,130 @@
+use super::SubMenu;
+use crate::replay::{self, ReplayFileError};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+pub struct ReplaysPlugin;
+
+/// This plugin is responsible for listing the recorded matches and opening one of them
+impl Plugin for ReplaysPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(list_replays)
+                .with_system(show_menu)
+                .with_system(go_back)
+                .with_system(open_replay),
+        );
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub enum ReplaysSubMenu {
+    Main(ViewModel),
+}
+
+impl Default for ReplaysSubMenu {
+    fn default() -> Self {
+        ReplaysSubMenu::Main(default())
+    }
+}
+
+#[derive(Default, PartialEq, Clone)]
+pub struct ViewModel {
+    /// `None` until the replays were listed.
+    replays: Option<Result<Vec<String>, ReplayFileError>>,
+    selected: Option<String>,
+    error: Option<ReplayFileError>,
+    back: bool,
+}
+
+fn list_replays(mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Replays(ReplaysSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.replays.is_none() {
+        view_model.replays = Some(replay::list_replays());
+    }
+}
+
+fn go_back(mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Replays(ReplaysSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    if view_model.back {
+        *sub_menu = SubMenu::Main;
+    }
+}
+
+fn open_replay(
+    mut commands: Commands,
+    mut sub_menu: ResMut<SubMenu>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Replays(ReplaysSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+    let name = match view_model.selected.take() {
+        Some(name) => name,
+        None => return,
+    };
+    let result = replay::load_replay(&name)
+        .and_then(|replay| replay::start_playback(&mut commands, &mut state, replay));
+    if let Err(error) = result {
+        view_model.error = Some(error);
+    }
+}
+
+fn show_menu(mut egui_ctx: ResMut<EguiContext>, mut sub_menu: ResMut<SubMenu>) {
+    let view_model = match &mut *sub_menu {
+        SubMenu::Replays(ReplaysSubMenu::Main(view_model)) => view_model,
+        _ => return,
+    };
+
+    egui::CentralPanel::default().show(egui_ctx.ctx_mut(), |ui| {
+        let center = ui.available_size() / 2.0;
+        ui.allocate_ui_at_rect(
+            egui::Rect::from_center_size(center.to_pos2(), egui::Vec2::new(400.0, 600.0)),
+            |ui| {
+                ui.push_id("Replays", |ui| {
+                    ui.heading("Replays");
+                });
+                ui.add_space(50.0);
+                match &view_model.replays {
+                    Some(Ok(replays)) if replays.is_empty() => {
+                        ui.label("No matches were recorded yet");
+                    }
+                    Some(Ok(replays)) => {
+                        egui::ScrollArea::vertical()
+                            .max_height(350.0)
+                            .show(ui, |ui| {
+                                for name in replays {
+                                    if ui.button(name).clicked() {
+                                        view_model.selected = Some(name.clone());
+                                    }
+                                }
+                            });
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::RED, error.to_string());
+                    }
+                    None => {
+                        ui.spinner();
+                    }
+                }
+                ui.add_space(20.0);
+                if ui.button("Back").clicked() {
+                    view_model.back = true;
+                }
+                if let Some(error) = &view_model.error {
+                    ui.add_space(20.0);
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                }
+            },
+        );
+    });
+}
@@ -0,0 +1,67 @@
+use bevy_egui::egui;
+use rules::rule_set::MAX_ROLL;
+use rules::RuleSet;
+
+const MAX_STARTING_PIGS: u32 = 50;
+const MAX_DICE: u8 = 4;
+const MAX_ROLLS: usize = 10;
+const MAX_OPENING_TURNS: usize = 5;
+
+/// Lets the player change the house rules of the next match.
+/// Every value that can be entered is valid.
+pub fn show_rule_set_editor(ui: &mut egui::Ui, rules: &mut RuleSet) {
+    ui.collapsing("House rules", |ui| {
+        ui.add(
+            egui::Slider::new(&mut rules.starting_pigs, 1..=MAX_STARTING_PIGS)
+                .text("Starting pigs"),
+        );
+
+        ui.add(egui::Slider::new(&mut rules.dice, 1..=MAX_DICE).text("Dice"));
+        let max_faces = MAX_ROLL / rules.dice;
+        rules.faces = rules.faces.min(max_faces);
+        ui.add(egui::Slider::new(&mut rules.faces, 2..=max_faces).text("Faces per die"));
+
+        ui.horizontal(|ui| {
+            ui.label("Rolls in the first turns:");
+            for rolls in rules.opening_rolls.iter_mut() {
+                ui.add(egui::DragValue::new(rolls).clamp_range(1..=MAX_ROLLS));
+            }
+            if ui
+                .add_enabled(!rules.opening_rolls.is_empty(), egui::Button::new("-"))
+                .clicked()
+            {
+                rules.opening_rolls.pop();
+            }
+            if ui
+                .add_enabled(
+                    rules.opening_rolls.len() < MAX_OPENING_TURNS,
+                    egui::Button::new("+"),
+                )
+                .clicked()
+            {
+                rules.opening_rolls.push(1);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut is_limited = rules.max_rolls_per_turn.is_some();
+            ui.checkbox(&mut is_limited, "Limit rolls per turn");
+            match (is_limited, rules.max_rolls_per_turn.as_mut()) {
+                (true, Some(max_rolls)) => {
+                    ui.add(egui::Slider::new(max_rolls, 1..=MAX_ROLLS));
+                }
+                (true, None) => rules.max_rolls_per_turn = Some(1),
+                (false, _) => rules.max_rolls_per_turn = None,
+            }
+        });
+
+        ui.checkbox(
+            &mut rules.bottomless_pig_hole,
+            "The pig hole keeps the pigs for good",
+        );
+
+        if ui.button("Classic rules").clicked() {
+            *rules = RuleSet::default();
+        }
+    });
+}
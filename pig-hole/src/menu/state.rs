@@ -1,11 +1,17 @@
+use crate::menu::account::AccountSubMenu;
 use crate::menu::browse_lobbies::BrowseLobbiesSubMenu;
 use crate::menu::create_lobby::CreateLobbySubMenu;
+use crate::menu::local_game::LocalGameSubMenu;
+use crate::menu::replays::ReplaysSubMenu;
 
 #[derive(PartialEq, Clone)]
 pub enum SubMenu {
     Main,
     CreateLobby(CreateLobbySubMenu),
     BrowseLobbies(BrowseLobbiesSubMenu),
+    LocalGame(LocalGameSubMenu),
+    Replays(ReplaysSubMenu),
+    Account(AccountSubMenu),
 }
 
 impl Default for SubMenu {
@@ -1,242 +1,1219 @@
+use crate::player::Intent;
+use crate::player_creation::{Controller, ResumedGame, Seat, Seats};
+use crate::GameState;
+pub use authority::AuthoritativeMatch;
+use bevy::ecs::schedule::ShouldRun;
 use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
 use bevy_renet::{
     renet::{
         ConnectToken, RenetClient, RenetConnectionConfig, RenetServer, ServerConfig, ServerEvent,
-        NETCODE_KEY_BYTES,
     },
     run_if_client_conected, RenetClientPlugin, RenetServerPlugin,
 };
 use bincode;
+pub use config::NetworkConfig;
+pub use error::NetworkError;
+use http::HttpError;
 use matchmaker_models::{
-    client_api::{LobbyCreation, LobbyResponse},
-    server_api::PROTOCOL_ID,
+    client_api::{
+        self, CreatedLobby, Credentials, JoinRequest, LobbyCreation, LobbyResponse, Session,
+    },
+    server_api::{
+        ConnectionData, LobbyEvent, LobbyReport, MatchResult, PlayerResult, ServerCredentials,
+        ServerHeartbeat, ServerRegistration, PROTOCOL_ID,
+    },
 };
+pub use protocol::TurnClock;
+use protocol::{ClientMessages, LobbyPlayer, ServerMessages, RELIABLE_CHANNEL};
 use renet::RenetError;
+use rules::dice::{self, Commitment, FairnessError};
+use rules::{Action, ReplayEvent, RuleSet};
 use serde::{Deserialize, Serialize};
+use std::net::UdpSocket;
+use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
-use std::{collections::HashMap, net::UdpSocket};
 
-const PRIVATE_KEY: &[u8; NETCODE_KEY_BYTES] = b"an example very very secret key."; // 32-bytes
+pub const MAX_PLAYERS: u8 = client_api::MAX_PLAYERS;
+/// Seconds between the moves a bot makes for a player that is away.
+const AFK_MOVE_DELAY: f32 = 1.0;
+/// Seconds between two heartbeats of a host, well below the time the matchmaker keeps a lobby.
+const HEARTBEAT_INTERVAL: f32 = 20.0;
 
+mod authority;
+mod config;
+mod error;
 mod http;
+mod protocol;
 
 pub struct NetworkingPlugin;
 
 impl Plugin for NetworkingPlugin {
     fn build(&self, app: &mut App) {
-        #[cfg(not(target_arch = "wasm32"))]
-        let is_host = {
-            let args: Vec<String> = std::env::args().collect();
-            let exec_type = &args[1];
-            match exec_type.as_str() {
-                "client" => false,
-                "server" => true,
-                _ => panic!("Invalid argument, must be \"client\" or \"server\"."),
-            }
-        };
-        #[cfg(target_arch = "wasm32")]
-        let is_host = false;
+        app.insert_resource(NetworkConfig::from_env())
+            .init_resource::<NetworkRole>()
+            .init_resource::<Lobby>()
+            .init_resource::<DiceAudit>()
+            .init_resource::<TurnClockState>()
+            .init_resource::<LobbySettings>()
+            .init_resource::<PendingClient>()
+            .add_event::<StartMatchEvent>()
+            .add_event::<KickPlayerEvent>()
+            .add_event::<LobbyEvent>()
+            .add_event::<ReconnectEvent>();
+
+        // Both plugins only do something while their resource exists,
+        // which is decided by the role chosen in the menu.
+        app.add_plugin(RenetServerPlugin);
+        app.add_system(server_update_system.with_run_criteria(run_if_server));
+        app.add_system(server_start_match.with_run_criteria(run_if_server));
+        app.add_system(server_kick_players.with_run_criteria(run_if_server));
+        app.add_system(server_run_turn_clock.with_run_criteria(run_if_server));
+        app.add_system(server_report_capacity.with_run_criteria(run_if_server));
+        app.add_system(server_report_match_end.with_run_criteria(run_if_server));
+        app.add_system(server_report_resumed_match.with_run_criteria(run_if_server));
+        app.add_system(server_report_to_matchmaker.with_run_criteria(run_if_server));
+
+        app.add_plugin(RenetClientPlugin);
+        app.add_system(client_receive_messages.with_run_criteria(run_if_client_conected));
+        app.add_system(client_send_intents.with_run_criteria(run_if_client_conected));
+
+        app.add_system(handle_transport_errors);
+        app.add_system(detect_disconnect);
+        app.add_system(start_reconnect);
+        app.add_system(poll_reconnect);
+
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(reset_network));
+    }
+}
+
+/// How this instance takes part in matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NetworkRole {
+    /// Hot-seat games on this machine only.
+    Offline,
+    /// Runs the server and plays through its own client.
+    Host,
+    /// Plays on the server of someone else.
+    Client,
+    /// Runs the server without playing.
+    Dedicated,
+}
+
+impl Default for NetworkRole {
+    fn default() -> Self {
+        Self::Offline
+    }
+}
 
-        app.insert_resource(Lobby::default());
+/// A lobby registered at the matchmaker together with its server.
+/// The host plays through `client`, a dedicated server has none.
+pub struct HostedLobby {
+    server: RenetServer,
+    client: Option<RenetClient>,
+    invite_code: Option<String>,
+    host_token: HostToken,
+}
+
+impl HostedLobby {
+    /// The code players need to join, if the lobby is unlisted.
+    pub fn invite_code(&self) -> Option<&str> {
+        self.invite_code.as_deref()
+    }
+}
+
+/// A lobby that is set up in the background.
+pub type PendingLobby = Arc<RwLock<Option<Result<HostedLobby, NetworkError>>>>;
+
+/// The lobby a client has joined, so it can join again after losing the connection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionInfo {
+    pub lobby: String,
+    /// The lobby is joined as the user of the session.
+    pub session: Session,
+    pub password: Option<String>,
+    /// Needed to join unlisted lobbies.
+    pub invite_code: Option<String>,
+}
+
+/// Inserted when the connection broke down, until the player reconnects or leaves.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionLost(pub NetworkError);
 
-        if is_host {
-            app.add_plugin(RenetServerPlugin);
-            app.insert_resource(create_renet_server());
-            app.add_system(server_update_system);
-            app.add_system(server_sync_players);
-            // app.add_system(move_players_system);
-        } else {
-            app.add_plugin(RenetClientPlugin);
-            // app.insert_resource(PlayerInput::default());
-            // app.add_system(player_input);
-            // app.add_system(client_send_input.with_run_criteria(run_if_client_conected));
-            app.add_system(client_sync_players.with_run_criteria(run_if_client_conected));
+/// The lobby this instance hosts, so a running match can be hosted again after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub lobby: String,
+    pub dedicated: bool,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Hidden from the lobby list, players join with an invite code instead.
+    #[serde(default)]
+    pub unlisted: bool,
+}
+
+/// Everything needed to host a running match again.
+/// The players get their seats back by joining the lobby with their accounts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostedMatch {
+    pub host: HostInfo,
+    players: Vec<LobbyPlayer>,
+    authoritative_match: AuthoritativeMatch,
+}
+
+impl HostedMatch {
+    pub fn new(
+        host: HostInfo,
+        lobby: &ServerLobby,
+        authoritative_match: &AuthoritativeMatch,
+    ) -> Self {
+        Self {
+            host,
+            players: lobby.players.clone(),
+            authoritative_match: authoritative_match.clone(),
         }
+    }
+
+    pub fn game(&self) -> &rules::GameState {
+        self.authoritative_match.game()
+    }
 
-        app.add_system(panic_on_error_system);
+    pub fn seat_count(&self) -> usize {
+        self.players.len()
+    }
+}
+
+/// Proves to the matchmaker that the reports about the hosted lobby come from its host.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct HostToken(String);
+
+/// Joins the last lobby again after the connection was lost.
+pub struct ReconnectEvent;
 
-        app.run();
+/// Present while a reconnect is in progress.
+pub struct Reconnecting;
+
+pub fn start_hosting(commands: &mut Commands, lobby: HostedLobby, host: HostInfo) {
+    commands.insert_resource(lobby.server);
+    commands.insert_resource(host);
+    commands.insert_resource(lobby.host_token);
+    commands.insert_resource(ServerLobby::default());
+    commands.insert_resource(LobbySettings::default());
+    commands.insert_resource(ServerTurnClock::default());
+    commands.insert_resource(HeartbeatTimer(Timer::from_seconds(
+        HEARTBEAT_INTERVAL,
+        true,
+    )));
+    // Started with the first message, for the lobby hosted now
+    commands.remove_resource::<MatchmakerReporter>();
+    match lobby.client {
+        Some(client) => {
+            commands.insert_resource(client);
+            commands.insert_resource(NetworkRole::Host);
+        }
+        None => commands.insert_resource(NetworkRole::Dedicated),
     }
 }
 
-#[derive(Debug, Component)]
-struct Player {
-    id: u64,
+pub fn start_client(commands: &mut Commands, client: RenetClient, info: ConnectionInfo) {
+    commands.insert_resource(client);
+    commands.insert_resource(info);
+    commands.insert_resource(NetworkRole::Client);
+    commands.remove_resource::<ConnectionLost>();
+}
+
+/// Hosts a saved match in a new lobby. It continues as soon as its players are back.
+pub fn resume_hosting(commands: &mut Commands, lobby: HostedLobby, hosted_match: HostedMatch) {
+    start_hosting(commands, lobby, hosted_match.host);
+    commands.insert_resource(ServerLobby {
+        players: hosted_match.players,
+        playing: true,
+    });
+    commands.insert_resource(hosted_match.authoritative_match);
+}
+
+/// Closes every connection of this instance and forgets everything about the last lobby.
+pub fn go_offline(commands: &mut Commands) {
+    commands.remove_resource::<RenetServer>();
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<ServerLobby>();
+    commands.remove_resource::<AuthoritativeMatch>();
+    commands.remove_resource::<ConnectionInfo>();
+    commands.remove_resource::<HostInfo>();
+    commands.remove_resource::<HostToken>();
+    commands.remove_resource::<ServerTurnClock>();
+    commands.remove_resource::<HeartbeatTimer>();
+    commands.remove_resource::<MatchmakerReporter>();
+    commands.remove_resource::<ConnectionLost>();
+    commands.remove_resource::<Reconnecting>();
+    commands.insert_resource(Lobby::default());
+    commands.insert_resource(DiceAudit::default());
+    commands.insert_resource(TurnClockState::default());
+    commands.insert_resource(NetworkRole::Offline);
+}
+
+fn reset_network(mut commands: Commands) {
+    go_offline(&mut commands);
+}
+
+/// A client that is created in the background, e.g. while the lobby is requested from the matchmaker.
+pub type PendingClient = Arc<RwLock<Option<Result<RenetClient, NetworkError>>>>;
+
+/// Players in the lobby as seen by the client.
+#[derive(Debug, Default)]
+pub struct Lobby {
+    pub players: Vec<LobbyPlayer>,
 }
 
+/// Players connected to the server, in order of connection.
 #[derive(Debug, Default)]
-struct Lobby {
-    players: HashMap<u64, Entity>,
+pub struct ServerLobby {
+    players: Vec<LobbyPlayer>,
+    playing: bool,
+}
+
+impl ServerLobby {
+    pub fn players(&self) -> &[LobbyPlayer] {
+        &self.players
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+}
+
+/// Limits and house rules the host has set for the lobby.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LobbySettings {
+    pub min_players: u8,
+    pub max_players: u8,
+    pub rules: RuleSet,
+    pub turn_clock: Option<TurnClock>,
+}
+
+impl Default for LobbySettings {
+    fn default() -> Self {
+        Self {
+            min_players: 2,
+            max_players: MAX_PLAYERS,
+            rules: RuleSet::default(),
+            turn_clock: None,
+        }
+    }
+}
+
+impl LobbySettings {
+    pub fn allows_player_count(&self, count: usize) -> bool {
+        (self.min_players as usize..=self.max_players as usize).contains(&count)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Component)]
-enum ServerMessages {
-    PlayerConnected { id: u64 },
-    PlayerDisconnected { id: u64 },
+/// The turn clock of the running match as seen by the host.
+struct ServerTurnClock {
+    /// Turn number and player of the turn the clock runs for.
+    turn: Option<(usize, usize)>,
+    /// Seconds since startup when the turn times out.
+    deadline: f64,
+    afk_move_timer: Timer,
 }
 
-pub async fn create_lobby(username: &str, lobby: &str) -> RenetClient {
+impl ServerTurnClock {
+    /// Whole seconds until the current turn of the game times out, if the clock runs for it.
+    fn seconds_left(&self, game: &rules::GameState, now: f64) -> Option<u32> {
+        let turn = (game.turn_number(), game.current_player());
+        (self.turn == Some(turn)).then(|| (self.deadline - now).max(0.0).ceil() as u32)
+    }
+}
+
+impl Default for ServerTurnClock {
+    fn default() -> Self {
+        Self {
+            turn: None,
+            deadline: 0.0,
+            afk_move_timer: Timer::from_seconds(AFK_MOVE_DELAY, true),
+        }
+    }
+}
+
+/// Tells the host when to let the matchmaker know that its lobby is still alive.
+struct HeartbeatTimer(Timer);
+
+/// Hands the messages for the matchmaker to the task that sends them, while hosting.
+struct MatchmakerReporter(async_channel::Sender<MatchmakerMessage>);
+
+enum MatchmakerMessage {
+    Heartbeat(ServerHeartbeat),
+    Report(LobbyReport),
+}
+
+/// What a client knows about the turn clock of the host.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TurnClockState {
+    /// Seconds since startup when the current turn times out.
+    deadline: Option<f64>,
+    /// Seats that are played by a bot until their players are back.
+    afk_seats: Vec<usize>,
+}
+
+impl TurnClockState {
+    pub fn seconds_left(&self, now: f64) -> Option<f64> {
+        self.deadline.map(|deadline| (deadline - now).max(0.0))
+    }
+
+    pub fn is_afk(&self, seat: usize) -> bool {
+        self.afk_seats.contains(&seat)
+    }
+}
+
+/// Starts a match on the host with every player currently in the lobby.
+pub struct StartMatchEvent;
+
+/// Removes the client with the given id from the lobby of the host.
+pub struct KickPlayerEvent(pub u64);
+
+pub fn run_if_server(server: Option<Res<RenetServer>>) -> ShouldRun {
+    match server {
+        Some(_) => ShouldRun::Yes,
+        None => ShouldRun::No,
+    }
+}
+
+/// Run criteria for everything that only happens when the rules are run on this machine alone.
+pub fn run_if_offline(role: Res<NetworkRole>) -> ShouldRun {
+    match *role {
+        NetworkRole::Offline => ShouldRun::Yes,
+        _ => ShouldRun::No,
+    }
+}
+
+pub async fn list_lobbies(config: &NetworkConfig) -> Result<Vec<client_api::Lobby>, NetworkError> {
+    Ok(http::get(&config.url("/lobbies")).await?)
+}
+
+/// The lobby an invite code leads to.
+pub async fn find_invite(
+    config: &NetworkConfig,
+    invite_code: &str,
+) -> Result<client_api::Lobby, NetworkError> {
+    let url = config.url(&format!("/invites/{}", invite_code));
+    let lobby: Option<client_api::Lobby> = http::get(&url).await?;
+    lobby.ok_or(NetworkError::LobbyNotFound)
+}
+
+/// Creates an account and logs it in.
+pub async fn register(
+    config: &NetworkConfig,
+    credentials: &Credentials,
+) -> Result<Session, NetworkError> {
+    http::put(&config.url("/accounts"), credentials)
+        .await
+        .map_err(|error| match error {
+            HttpError::Status(400) => NetworkError::InvalidUsername,
+            HttpError::Status(409) => NetworkError::AccountTaken,
+            error => error.into(),
+        })
+}
+
+pub async fn log_in(
+    config: &NetworkConfig,
+    credentials: &Credentials,
+) -> Result<Session, NetworkError> {
+    http::put(&config.url("/sessions"), credentials)
+        .await
+        .map_err(|error| match error {
+            HttpError::Status(401) => NetworkError::WrongCredentials,
+            error => error.into(),
+        })
+}
+
+/// Creates a lobby, registers a server for it at the matchmaker and, unless `dedicated`, joins it.
+/// The user of the session hosts the lobby.
+pub async fn host_lobby(
+    config: &NetworkConfig,
+    session: &Session,
+    host: &HostInfo,
+) -> Result<HostedLobby, NetworkError> {
     let request = LobbyCreation {
-        name: lobby.to_string(),
-        host: username.to_string(),
+        name: host.lobby.clone(),
+        max_players: MAX_PLAYERS,
+        password: host.password.clone(),
+        unlisted: host.unlisted,
     };
-    let url = "http://127.0.0.1:8000/lobbies";
-    let request = http::put(url, request).await;
-    create_client(request)
+    let created: CreatedLobby =
+        http::put_as(&config.url("/lobbies"), Some(&session.token), request)
+            .await
+            .map_err(|error| match error {
+                HttpError::Status(400) => NetworkError::InvalidLobbyName,
+                HttpError::Status(401) => NetworkError::SessionExpired,
+                error => error.into(),
+            })?;
+
+    let registration = ServerRegistration {
+        address: config.public_address,
+        host_token: created.host_token.clone(),
+    };
+    let url = config.url(&format!("/lobbies/{}/server", host.lobby));
+    let credentials = http::put(&url, registration).await?;
+    let server = create_renet_server(config, credentials)?;
+
+    let info = ConnectionInfo {
+        lobby: host.lobby.clone(),
+        session: session.clone(),
+        password: host.password.clone(),
+        invite_code: created.invite_code.clone(),
+    };
+    let client = match host.dedicated {
+        true => None,
+        false => Some(join_lobby(config, &info).await?),
+    };
+    Ok(HostedLobby {
+        server,
+        client,
+        invite_code: created.invite_code,
+        host_token: HostToken(created.host_token),
+    })
 }
 
-pub async fn join_lobby(username: &str, lobby: &str) -> RenetClient {
-    let request = username.to_string();
-    let url = format!("http://127.0.0.1:8000/lobbies/{}", lobby);
-    let request = http::put(&url, request).await;
+pub async fn join_lobby(
+    config: &NetworkConfig,
+    info: &ConnectionInfo,
+) -> Result<RenetClient, NetworkError> {
+    let request = JoinRequest {
+        password: info.password.clone(),
+        invite_code: info.invite_code.clone(),
+    };
+    let url = config.url(&format!("/lobbies/{}", info.lobby));
+    let request = http::put_as(&url, Some(&info.session.token), request)
+        .await
+        .map_err(|error| match error {
+            HttpError::Status(401) => NetworkError::SessionExpired,
+            HttpError::Status(409) => NetworkError::UsernameTaken,
+            error => error.into(),
+        })?;
     create_client(request)
 }
 
-pub fn create_renet_server() -> RenetServer {
-    let server_addr = "127.0.0.1:1337".parse().unwrap();
-    let socket = UdpSocket::bind(server_addr).unwrap();
+fn create_renet_server(
+    config: &NetworkConfig,
+    credentials: ServerCredentials,
+) -> Result<RenetServer, NetworkError> {
+    let socket_error = |error: std::io::Error| NetworkError::Socket(error.to_string());
+    let socket = UdpSocket::bind(config.bind_address).map_err(socket_error)?;
     let connection_config = RenetConnectionConfig::default();
-    let server_config = ServerConfig::new(64, PROTOCOL_ID, server_addr, *PRIVATE_KEY);
+    let server_config = ServerConfig::new(
+        64,
+        PROTOCOL_ID,
+        config.public_address,
+        credentials.private_key,
+    );
     let current_time = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
-    bevy::log::info!("Server listening on {}", server_addr);
-    RenetServer::new(current_time, server_config, connection_config, socket).unwrap()
+    bevy::log::info!(
+        "Server listening on {}, reachable at {}",
+        config.bind_address,
+        config.public_address
+    );
+    RenetServer::new(current_time, server_config, connection_config, socket).map_err(socket_error)
 }
 
-fn create_client(lobby_response: LobbyResponse) -> RenetClient {
-    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+fn create_client(lobby_response: LobbyResponse) -> Result<RenetClient, NetworkError> {
+    let socket_error = |error: std::io::Error| NetworkError::Socket(error.to_string());
+    let socket = UdpSocket::bind("127.0.0.1:0").map_err(socket_error)?;
     let connection_config = RenetConnectionConfig::default();
     let current_time = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
 
-    let token = read_token(&lobby_response.token);
+    let token = read_token(&lobby_response.token)?;
     let client_id = lobby_response.client_id;
 
-    RenetClient::new(current_time, socket, client_id, token, connection_config).unwrap()
+    RenetClient::new(current_time, socket, client_id, token, connection_config)
+        .map_err(socket_error)
 }
 
-fn read_token(mut bytes: &[u8]) -> ConnectToken {
-    let token = ConnectToken::read(&mut bytes).unwrap();
-    token
+fn read_token(mut bytes: &[u8]) -> Result<ConnectToken, NetworkError> {
+    ConnectToken::read(&mut bytes).map_err(|error| NetworkError::InvalidToken(error.to_string()))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn server_update_system(
     mut server_events: EventReader<ServerEvent>,
-    mut commands: Commands,
-    mut lobby: ResMut<Lobby>,
+    mut lobby: ResMut<ServerLobby>,
     mut server: ResMut<RenetServer>,
+    mut authoritative_match: Option<ResMut<AuthoritativeMatch>>,
+    settings: Res<LobbySettings>,
+    clock: Option<Res<ServerTurnClock>>,
+    time: Res<Time>,
+    mut reports: EventWriter<LobbyEvent>,
 ) {
     for event in server_events.iter() {
         match event {
-            ServerEvent::ClientConnected(id, _) => {
-                println!("Player {} connected.", id);
-                // Spawn player cube
-                let player_entity = commands
-                    .spawn()
-                    // .insert(PlayerInput::default())
-                    .insert(Player { id: *id })
-                    .id();
-
-                // We could send an InitState with all the players id and positions for the client
-                // but this is easier to do.
-                for &player_id in lobby.players.keys() {
-                    let message =
-                        bincode::serialize(&ServerMessages::PlayerConnected { id: player_id })
-                            .unwrap();
-                    server.send_message(*id, 0, message);
-                }
-
-                lobby.players.insert(*id, player_entity);
-
-                let message =
-                    bincode::serialize(&ServerMessages::PlayerConnected { id: *id }).unwrap();
-                server.broadcast_message(0, message);
+            ServerEvent::ClientConnected(id, user_data) => {
+                let ConnectionData {
+                    user_id, username, ..
+                } = ConnectionData::from_user_data(user_data);
+                if let (true, Some(authoritative_match)) =
+                    (lobby.playing, authoritative_match.as_mut())
+                {
+                    let connected = server.clients_id();
+                    let seat = lobby.players.iter_mut().find(|player| {
+                        player.user_id == user_id && !connected.contains(&player.id)
+                    });
+                    if let Some(player) = seat {
+                        println!("Player {} ({}) rejoined as {}.", player.id, username, id);
+                        authoritative_match.reseat(player.id, *id);
+                        player.id = *id;
+                        let game = authoritative_match.game();
+                        let now = time.seconds_since_startup();
+                        let message = ServerMessages::Rejoined {
+                            seats: lobby.players.clone(),
+                            game: game.clone(),
+                            dice_commitment: authoritative_match.dice().commitment(),
+                            rolls: authoritative_match.rolls().to_vec(),
+                            seconds_left: clock
+                                .as_ref()
+                                .and_then(|clock| clock.seconds_left(game, now)),
+                            afk_seats: authoritative_match.afk_seats(),
+                        };
+                        send_to_client(&mut server, *id, &message);
+                        reports.send(LobbyEvent::PlayerJoined { username });
+                        continue;
+                    }
+                }
+                if lobby.playing || lobby.players.len() >= settings.max_players as usize {
+                    println!(
+                        "Player {} ({}) rejected, the lobby is closed.",
+                        id, username
+                    );
+                    server.disconnect(*id);
+                    continue;
+                }
+                println!("Player {} ({}) connected.", id, username);
+
+                for player in lobby.players.iter() {
+                    let message = ServerMessages::PlayerConnected {
+                        player: player.clone(),
+                    };
+                    send_to_client(&mut server, *id, &message);
+                }
+
+                let player = LobbyPlayer {
+                    id: *id,
+                    user_id,
+                    username,
+                };
+                lobby.players.push(player.clone());
+                reports.send(LobbyEvent::PlayerJoined {
+                    username: player.username.clone(),
+                });
+                broadcast(&mut server, &ServerMessages::PlayerConnected { player });
             }
             ServerEvent::ClientDisconnected(id) => {
                 println!("Player {} disconnected.", id);
-                if let Some(player_entity) = lobby.players.remove(id) {
-                    commands.entity(player_entity).despawn();
+                // During a match the seat is kept, so the player can rejoin
+                if !lobby.playing {
+                    // Rejected clients never made it into the lobby
+                    if let Some(player) = lobby.players.iter().find(|player| player.id == *id) {
+                        let username = player.username.clone();
+                        reports.send(LobbyEvent::PlayerLeft { username });
+                    }
+                    lobby.players.retain(|player| player.id != *id);
                 }
-
-                let message =
-                    bincode::serialize(&ServerMessages::PlayerDisconnected { id: *id }).unwrap();
-                server.broadcast_message(0, message);
+                broadcast(&mut server, &ServerMessages::PlayerDisconnected { id: *id });
             }
         }
     }
 
     for client_id in server.clients_id().into_iter() {
-        while let Some(_message) = server.receive_message(client_id, 0) {
-            /*
-            let player_input: PlayerInput = bincode::deserialize(&message).unwrap();
-            if let Some(player_entity) = lobby.players.get(&client_id) {
-                commands.entity(*player_entity).insert(player_input);
+        while let Some(message) = server.receive_message(client_id, RELIABLE_CHANNEL) {
+            let message: ClientMessages = match bincode::deserialize(&message) {
+                Ok(message) => message,
+                Err(error) => {
+                    warn!("Received invalid message from {}: {}", client_id, error);
+                    continue;
+                }
+            };
+            match (message, authoritative_match.as_mut()) {
+                (ClientMessages::Intent(intent), Some(authoritative_match)) => {
+                    let seat = authoritative_match.seat_of(client_id);
+                    let was_afk = seat.map_or(false, |seat| authoritative_match.is_afk(seat));
+                    match authoritative_match.handle_intent(client_id, intent) {
+                        Ok((action, events)) => {
+                            broadcast_action(&mut server, authoritative_match, action, events);
+                            if let (true, Some(seat)) = (was_afk, seat) {
+                                let message = ServerMessages::AfkChanged { seat, afk: false };
+                                broadcast(&mut server, &message);
+                            }
+                        }
+                        Err(error) => {
+                            let reason = error.to_string();
+                            let message = ServerMessages::IntentRejected { reason };
+                            send_to_client(&mut server, client_id, &message);
+                        }
+                    }
+                }
+                (ClientMessages::RequestSync, Some(authoritative_match)) => {
+                    let game = authoritative_match.game().clone();
+                    send_to_client(&mut server, client_id, &ServerMessages::Sync { game });
+                }
+                (message, None) => warn!("Ignoring {:?}, no match is running", message),
             }
-            */
         }
     }
 }
 
-fn server_sync_players(mut server: ResMut<RenetServer>, query: Query<(&Transform, &Player)>) {
-    let mut players: HashMap<u64, [f32; 3]> = HashMap::new();
-    for (transform, player) in query.iter() {
-        players.insert(player.id, transform.translation.into());
+fn server_start_match(
+    mut commands: Commands,
+    mut start_match_events: EventReader<StartMatchEvent>,
+    mut lobby: ResMut<ServerLobby>,
+    settings: Res<LobbySettings>,
+    mut server: ResMut<RenetServer>,
+    mut reports: EventWriter<LobbyEvent>,
+) {
+    for _event in start_match_events.iter() {
+        if lobby.players.is_empty() || !settings.allows_player_count(lobby.players.len()) {
+            warn!("Cannot start a match with {} players", lobby.players.len());
+            continue;
+        }
+        if let Err(error) = settings.rules.validate() {
+            warn!("Cannot start a match with these rules: {}", error);
+            continue;
+        }
+        let seats = lobby.players.clone();
+        let client_ids = seats.iter().map(|player| player.id).collect();
+        let authoritative_match =
+            AuthoritativeMatch::new(client_ids, settings.rules.clone(), settings.turn_clock);
+        let dice_commitment = authoritative_match.dice().commitment();
+        commands.insert_resource(authoritative_match);
+        commands.insert_resource(ServerTurnClock::default());
+        lobby.playing = true;
+        reports.send(LobbyEvent::MatchStarted);
+        broadcast(
+            &mut server,
+            &ServerMessages::MatchStarted {
+                seats,
+                dice_commitment,
+                rules: settings.rules.clone(),
+            },
+        );
     }
+}
 
-    let sync_message = bincode::serialize(&players).unwrap();
-    server.broadcast_message(1, sync_message);
+/// Ends turns that took too long and plays for the players that are away.
+fn server_run_turn_clock(
+    time: Res<Time>,
+    clock: Option<ResMut<ServerTurnClock>>,
+    authoritative_match: Option<ResMut<AuthoritativeMatch>>,
+    mut server: ResMut<RenetServer>,
+) {
+    let (mut clock, mut authoritative_match) = match (clock, authoritative_match) {
+        (Some(clock), Some(authoritative_match)) => (clock, authoritative_match),
+        _ => return,
+    };
+    let turn_clock = match authoritative_match.turn_clock() {
+        Some(turn_clock) if !authoritative_match.game().is_over() => turn_clock,
+        _ => return,
+    };
+    let now = time.seconds_since_startup();
+    let game = authoritative_match.game();
+    let seat = game.current_player();
+    let turn = (game.turn_number(), seat);
+    if clock.turn != Some(turn) {
+        clock.turn = Some(turn);
+        clock.deadline = now + f64::from(turn_clock.seconds);
+        let message = ServerMessages::TurnClockStarted {
+            seconds: turn_clock.seconds,
+        };
+        broadcast(&mut server, &message);
+    }
+
+    if authoritative_match.is_afk(seat) {
+        if clock.afk_move_timer.tick(time.delta()).just_finished() {
+            if let Some((action, events)) = authoritative_match.play_for_afk_player() {
+                broadcast_action(&mut server, &authoritative_match, action, events);
+            }
+        }
+        return;
+    }
+    if now < clock.deadline {
+        return;
+    }
+    println!("Player {} ran out of time.", seat);
+    for (action, events) in authoritative_match.time_out() {
+        broadcast_action(&mut server, &authoritative_match, action, events);
+    }
+    if authoritative_match.is_afk(seat) {
+        broadcast(&mut server, &ServerMessages::AfkChanged { seat, afk: true });
+    }
+}
+
+/// Lets the matchmaker know how many players may join, so it does not hand out too many seats.
+fn server_report_capacity(
+    settings: Res<LobbySettings>,
+    mut reports: EventWriter<LobbyEvent>,
+    mut reported: Local<Option<u8>>,
+) {
+    if !settings.is_changed() || *reported == Some(settings.max_players) {
+        return;
+    }
+    *reported = Some(settings.max_players);
+    reports.send(LobbyEvent::CapacityChanged {
+        max_players: settings.max_players,
+    });
 }
 
-fn client_sync_players(
+/// Reports the result once the running match is over.
+fn server_report_match_end(
+    authoritative_match: Option<Res<AuthoritativeMatch>>,
+    lobby: Res<ServerLobby>,
+    mut reports: EventWriter<LobbyEvent>,
+    mut reported: Local<bool>,
+) {
+    let game = match authoritative_match {
+        Some(authoritative_match) if authoritative_match.game().is_over() => {
+            authoritative_match.game().clone()
+        }
+        _ => {
+            *reported = false;
+            return;
+        }
+    };
+    if *reported {
+        return;
+    }
+    *reported = true;
+    // The players keep the order of their seats, even when they rejoin
+    let username = |seat: usize| {
+        lobby
+            .players
+            .get(seat)
+            .map(|player| player.username.clone())
+            .unwrap_or_default()
+    };
+    let players = (0..game.player_count())
+        .map(|seat| PlayerResult {
+            username: username(seat),
+            pigs: game.pig_count(seat),
+        })
+        .collect();
+    reports.send(LobbyEvent::MatchFinished(MatchResult {
+        winner: game.winner().map(username),
+        players,
+    }));
+}
+
+/// Tells the matchmaker that a resumed match is running and who is seated in it,
+/// so the seats are kept for its players while they come back.
+fn server_report_resumed_match(lobby: Res<ServerLobby>, mut reports: EventWriter<LobbyEvent>) {
+    if !lobby.is_added() || !lobby.playing {
+        return;
+    }
+    for player in lobby.players.iter() {
+        reports.send(LobbyEvent::PlayerJoined {
+            username: player.username.clone(),
+        });
+    }
+    reports.send(LobbyEvent::MatchStarted);
+}
+
+/// Tells the matchmaker what happens in the lobby, and that it is still alive when nothing does.
+/// The matchmaker forgets lobbies whose hosts went silent.
+#[allow(clippy::too_many_arguments)]
+fn server_report_to_matchmaker(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut reports: EventReader<LobbyEvent>,
+    timer: Option<ResMut<HeartbeatTimer>>,
+    host: Option<Res<HostInfo>>,
+    host_token: Option<Res<HostToken>>,
+    reporter: Option<Res<MatchmakerReporter>>,
+    task_pool: Res<IoTaskPool>,
+    config: Res<NetworkConfig>,
+) {
+    let (mut timer, host, host_token) = match (timer, host, host_token) {
+        (Some(timer), Some(host), Some(host_token)) => (timer, host, host_token),
+        _ => return,
+    };
+    let mut messages: Vec<MatchmakerMessage> = reports
+        .iter()
+        .map(|event| {
+            MatchmakerMessage::Report(LobbyReport {
+                event: event.clone(),
+                host_token: host_token.0.clone(),
+            })
+        })
+        .collect();
+    if !messages.is_empty() {
+        // Every report keeps the lobby alive as well
+        timer.0.reset();
+    } else if timer.0.tick(time.delta()).just_finished() {
+        messages.push(MatchmakerMessage::Heartbeat(ServerHeartbeat {
+            host_token: host_token.0.clone(),
+        }));
+    } else {
+        return;
+    }
+    let sender = match reporter {
+        Some(reporter) => reporter.0.clone(),
+        None => {
+            let (sender, receiver) = async_channel::unbounded();
+            let config = config.clone();
+            let lobby = host.lobby.clone();
+            task_pool
+                .spawn(send_to_matchmaker(config, lobby, receiver))
+                .detach();
+            commands.insert_resource(MatchmakerReporter(sender.clone()));
+            sender
+        }
+    };
+    for message in messages {
+        if sender.try_send(message).is_err() {
+            warn!("The reports to the matchmaker were stopped");
+        }
+    }
+}
+
+/// Sends the messages of the host one after another, so the matchmaker sees them in order.
+/// Ends once the host goes offline and the sender is dropped.
+async fn send_to_matchmaker(
+    config: NetworkConfig,
+    lobby: String,
+    messages: async_channel::Receiver<MatchmakerMessage>,
+) {
+    let heartbeat_url = config.url(&format!("/lobbies/{}/heartbeat", lobby));
+    let report_url = config.url(&format!("/lobbies/{}/events", lobby));
+    while let Ok(message) = messages.recv().await {
+        // Failures are logged by `http`, the next heartbeat may get through
+        let _: Result<(), _> = match message {
+            MatchmakerMessage::Heartbeat(heartbeat) => http::put(&heartbeat_url, heartbeat).await,
+            MatchmakerMessage::Report(report) => http::put(&report_url, report).await,
+        };
+    }
+}
+
+fn server_kick_players(
+    mut kick_player_events: EventReader<KickPlayerEvent>,
+    mut server: ResMut<RenetServer>,
+) {
+    for KickPlayerEvent(id) in kick_player_events.iter() {
+        println!("Kicking player {}.", id);
+        server.disconnect(*id);
+    }
+}
+
+fn send_to_client(server: &mut RenetServer, client_id: u64, message: &ServerMessages) {
+    let message = bincode::serialize(message).unwrap();
+    server.send_message(client_id, RELIABLE_CHANNEL, message);
+}
+
+fn broadcast(server: &mut RenetServer, message: &ServerMessages) {
+    let message = bincode::serialize(message).unwrap();
+    server.broadcast_message(RELIABLE_CHANNEL, message);
+}
+
+/// Tells every client about an accepted action, and reveals the dice seed once the match is over.
+fn broadcast_action(
+    server: &mut RenetServer,
+    authoritative_match: &AuthoritativeMatch,
+    action: Action,
+    events: Vec<rules::Event>,
+) {
+    broadcast(server, &ServerMessages::ActionApplied { action, events });
+    if let Some(seed) = authoritative_match.revealed_seed() {
+        broadcast(server, &ServerMessages::SeedRevealed { seed });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn client_receive_messages(
     mut commands: Commands,
     mut client: ResMut<RenetClient>,
     mut lobby: ResMut<Lobby>,
+    mut dice_audit: ResMut<DiceAudit>,
+    mut game: Option<ResMut<rules::GameState>>,
+    mut state: ResMut<State<GameState>>,
+    mut replay_events: EventWriter<ReplayEvent>,
+    mut turn_clock: ResMut<TurnClockState>,
+    time: Res<Time>,
 ) {
-    while let Some(message) = client.receive_message(0) {
-        let server_message = bincode::deserialize(&message).unwrap();
+    while let Some(message) = client.receive_message(RELIABLE_CHANNEL) {
+        let server_message: ServerMessages = match bincode::deserialize(&message) {
+            Ok(message) => message,
+            Err(error) => {
+                warn!("Received invalid message from server: {}", error);
+                continue;
+            }
+        };
         match server_message {
-            ServerMessages::PlayerConnected { id } => {
-                println!("Player {} connected.", id);
-                let player_entity = commands.spawn().id();
-
-                lobby.players.insert(id, player_entity);
+            ServerMessages::PlayerConnected { player } => {
+                println!("Player {} ({}) connected.", player.id, player.username);
+                replay_events.send(ReplayEvent::Joined(player.username.clone()));
+                lobby.players.retain(|other| other.id != player.id);
+                lobby.players.push(player);
             }
             ServerMessages::PlayerDisconnected { id } => {
                 println!("Player {} disconnected.", id);
-                if let Some(player_entity) = lobby.players.remove(&id) {
-                    commands.entity(player_entity).despawn();
+                if let Some(player) = lobby.players.iter().find(|player| player.id == id) {
+                    replay_events.send(ReplayEvent::Left(player.username.clone()));
+                }
+                lobby.players.retain(|player| player.id != id);
+            }
+            ServerMessages::MatchStarted {
+                seats,
+                dice_commitment,
+                rules,
+            } => {
+                if let Err(error) = check_match(&rules, seats.len(), seats.len()) {
+                    commands.insert_resource(ConnectionLost(error));
+                    return;
+                }
+                info!("Dice commitment: {}", dice::to_hex(&dice_commitment));
+                *dice_audit = DiceAudit::new(dice_commitment, rules.clone());
+                *turn_clock = default();
+                commands.insert_resource(rules);
+                commands.insert_resource(get_seats(seats, client.client_id()));
+                if let Err(error) = state.set(GameState::Playing) {
+                    warn!("Failed to start match: {:?}", error);
+                }
+            }
+            ServerMessages::Rejoined {
+                seats,
+                game,
+                dice_commitment,
+                rolls,
+                seconds_left,
+                afk_seats,
+            } => {
+                if let Err(error) = check_match(game.rules(), seats.len(), game.player_count()) {
+                    commands.insert_resource(ConnectionLost(error));
+                    return;
+                }
+                info!("Rejoined the match with {} players", seats.len());
+                *dice_audit = DiceAudit {
+                    rolls,
+                    ..DiceAudit::new(dice_commitment, game.rules().clone())
+                };
+                *turn_clock = TurnClockState {
+                    deadline: seconds_left
+                        .map(|seconds| time.seconds_since_startup() + f64::from(seconds)),
+                    afk_seats,
+                };
+                commands.insert_resource(game.rules().clone());
+                if *state.current() == GameState::Playing {
+                    replay_events.send(ReplayEvent::Synced(game.clone()));
+                    commands.insert_resource(game);
+                    continue;
+                }
+                // The host resumed a saved match, which this client joins from the menu
+                commands.insert_resource(get_seats(seats, client.client_id()));
+                commands.insert_resource(ResumedGame(game));
+                if let Err(error) = state.set(GameState::Playing) {
+                    warn!("Failed to resume match: {:?}", error);
+                }
+            }
+            ServerMessages::ActionApplied { action, events } => {
+                if let Action::Roll(roll) = action {
+                    dice_audit.rolls.push(roll);
+                }
+                let is_in_sync = match game.as_mut() {
+                    Some(game) => game.apply(action).as_ref() == Ok(&events),
+                    None => false,
+                };
+                if events
+                    .iter()
+                    .any(|event| matches!(event, rules::Event::TurnEnded { .. }))
+                {
+                    turn_clock.deadline = None;
+                }
+                if is_in_sync {
+                    replay_events.send(ReplayEvent::Action(action));
+                } else {
+                    warn!("Out of sync with the server, requesting the full game state");
+                    send_to_server(&mut client, &ClientMessages::RequestSync);
+                }
+            }
+            ServerMessages::IntentRejected { reason } => {
+                warn!("The server rejected the last move: {}", reason);
+                send_to_server(&mut client, &ClientMessages::RequestSync);
+            }
+            ServerMessages::Sync { game: synced } => {
+                // The seats were given out when the match started and cannot change
+                let seat_count = game
+                    .as_ref()
+                    .map_or(synced.player_count(), |game| game.player_count());
+                if let Err(error) = check_match(synced.rules(), seat_count, synced.player_count()) {
+                    commands.insert_resource(ConnectionLost(error));
+                    return;
+                }
+                replay_events.send(ReplayEvent::Synced(synced.clone()));
+                commands.insert_resource(synced);
+            }
+            ServerMessages::SeedRevealed { seed } => {
+                let result = dice_audit.verify(&seed);
+                match &result {
+                    Ok(()) => info!("All {} rolls were fair", dice_audit.rolls.len()),
+                    Err(error) => warn!("The dice were manipulated: {}", error),
+                }
+                dice_audit.result = Some(result);
+            }
+            ServerMessages::TurnClockStarted { seconds } => {
+                turn_clock.deadline = Some(time.seconds_since_startup() + f64::from(seconds));
+            }
+            ServerMessages::AfkChanged { seat, afk } => {
+                turn_clock.afk_seats.retain(|&other| other != seat);
+                if afk {
+                    turn_clock.afk_seats.push(seat);
                 }
             }
         }
     }
+}
 
-    while let Some(message) = client.receive_message(1) {
-        let players: HashMap<u64, [f32; 3]> = bincode::deserialize(&message).unwrap();
-        for (player_id, translation) in players.iter() {
-            if let Some(player_entity) = lobby.players.get(player_id) {
-                let transform = Transform {
-                    translation: (*translation).into(),
-                    ..Default::default()
-                };
-                commands.entity(*player_entity).insert(transform);
-            }
+/// Checks what the host sent before it goes on the board, where it would crash the game otherwise.
+fn check_match(
+    rules: &RuleSet,
+    seat_count: usize,
+    player_count: usize,
+) -> Result<(), NetworkError> {
+    if let Err(error) = rules.validate() {
+        return Err(NetworkError::InvalidMatch(error.to_string()));
+    }
+    if seat_count == 0 || seat_count != player_count {
+        return Err(NetworkError::InvalidMatch(format!(
+            "{} seats do not fit a game of {} players",
+            seat_count, player_count
+        )));
+    }
+    Ok(())
+}
+
+fn get_seats(players: Vec<LobbyPlayer>, own_id: u64) -> Seats {
+    let seats = players
+        .into_iter()
+        .map(|player| Seat {
+            controller: if player.id == own_id {
+                Controller::Local
+            } else {
+                Controller::Remote
+            },
+            name: player.username,
+        })
+        .collect();
+    Seats(seats)
+}
+
+/// What a client needs to check the rolls of the server once the seed is revealed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiceAudit {
+    commitment: Option<Commitment>,
+    /// The rules decide how many dice make up a roll.
+    rules: RuleSet,
+    /// Every roll of the match, in order.
+    rolls: Vec<u8>,
+    pub result: Option<Result<(), FairnessError>>,
+}
+
+impl DiceAudit {
+    fn new(commitment: Commitment, rules: RuleSet) -> Self {
+        Self {
+            commitment: Some(commitment),
+            rules,
+            ..default()
+        }
+    }
+
+    fn verify(&self, seed: &dice::Seed) -> Result<(), FairnessError> {
+        match &self.commitment {
+            Some(commitment) => dice::verify(commitment, seed, &self.rolls, &self.rules),
+            None => Err(FairnessError::WrongSeed),
         }
     }
 }
 
-/*
-fn client_send_input(player_input: Res<PlayerInput>, mut client: ResMut<RenetClient>) {
-    let input_message = bincode::serialize(&*player_input).unwrap();
+fn client_send_intents(mut intents: EventReader<Intent>, mut client: ResMut<RenetClient>) {
+    for intent in intents.iter() {
+        send_to_server(&mut client, &ClientMessages::Intent(*intent));
+    }
+}
 
-    client.send_message(0, input_message);
+fn send_to_server(client: &mut RenetClient, message: &ClientMessages) {
+    let message = bincode::serialize(message).unwrap();
+    client.send_message(RELIABLE_CHANNEL, message);
 }
-*/
 
-// If any error is found we just panic
-fn panic_on_error_system(mut renet_error: EventReader<RenetError>) {
-    for e in renet_error.iter() {
-        panic!("{}", e);
+fn start_reconnect(
+    mut commands: Commands,
+    mut reconnect_events: EventReader<ReconnectEvent>,
+    task_pool: Res<IoTaskPool>,
+    config: Res<NetworkConfig>,
+    pending_client: Res<PendingClient>,
+    info: Option<Res<ConnectionInfo>>,
+    reconnecting: Option<Res<Reconnecting>>,
+) {
+    if reconnect_events.iter().last().is_none() || reconnecting.is_some() {
+        return;
+    }
+    let info = match info {
+        Some(info) => info.clone(),
+        None => {
+            warn!("Cannot reconnect, this instance never joined a lobby");
+            return;
+        }
+    };
+    commands.remove_resource::<RenetClient>();
+    commands.insert_resource(Reconnecting);
+    // The server sends the players of the lobby again
+    commands.insert_resource(Lobby::default());
+
+    let config = config.clone();
+    let inner_client = pending_client.clone();
+    task_pool
+        .spawn(async move {
+            let client = join_lobby(&config, &info).await;
+            *inner_client.write().unwrap() = Some(client);
+        })
+        .detach();
+}
+
+fn poll_reconnect(
+    mut commands: Commands,
+    pending_client: Res<PendingClient>,
+    info: Option<Res<ConnectionInfo>>,
+    reconnecting: Option<Res<Reconnecting>>,
+) {
+    let info = match (reconnecting, info) {
+        (Some(_), Some(info)) => info.clone(),
+        _ => return,
+    };
+    let result = pending_client.write().unwrap().take();
+    match result {
+        Some(Ok(client)) => start_client(&mut commands, client, info),
+        Some(Err(error)) => commands.insert_resource(ConnectionLost(error)),
+        None => return,
+    }
+    commands.remove_resource::<Reconnecting>();
+}
+
+fn handle_transport_errors(mut commands: Commands, mut renet_errors: EventReader<RenetError>) {
+    for error in renet_errors.iter() {
+        error!("Network error: {}", error);
+        commands.insert_resource(ConnectionLost(NetworkError::Transport(error.to_string())));
+    }
+}
+
+fn detect_disconnect(
+    mut commands: Commands,
+    client: Option<Res<RenetClient>>,
+    connection_lost: Option<Res<ConnectionLost>>,
+) {
+    if connection_lost.is_some() {
+        return;
+    }
+    if let Some(reason) = client.and_then(|client| client.disconnected()) {
+        warn!("Disconnected from the server: {}", reason);
+        commands.insert_resource(ConnectionLost(NetworkError::Disconnected(
+            reason.to_string(),
+        )));
     }
 }
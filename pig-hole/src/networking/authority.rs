@@ -0,0 +1,306 @@
+use super::protocol::TurnClock;
+use crate::player::Intent;
+use rand::Rng;
+use rules::bot::{self, Difficulty};
+use rules::dice::Seed;
+use rules::{Action, Event, FairDice, Phase, RuleError, RuleSet};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The game state of a networked match as seen by the host.
+/// Every intent of the clients is validated against it before it is broadcast.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthoritativeMatch {
+    /// Client ids in turn order
+    seats: Vec<u64>,
+    game: rules::GameState,
+    dice: FairDice,
+    /// Every roll so far, for clients that join again and want to audit the dice.
+    rolls: Vec<u8>,
+    /// How long players may take for their turn, if limited at all.
+    #[serde(default)]
+    turn_clock: Option<TurnClock>,
+    /// Turns in a row every seat has run out of time.
+    #[serde(default)]
+    timeouts: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntentError {
+    NotSeated,
+    NotYourTurn,
+    Rule(RuleError),
+}
+
+impl Display for IntentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntentError::NotSeated => write!(f, "You are not playing in this match"),
+            IntentError::NotYourTurn => write!(f, "It is not your turn"),
+            IntentError::Rule(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl AuthoritativeMatch {
+    pub fn new(seats: Vec<u64>, rules: RuleSet, turn_clock: Option<TurnClock>) -> Self {
+        let dice = FairDice::for_rules(rand::thread_rng().gen(), &rules);
+        let game = rules::GameState::with_rules(seats.len(), rules);
+        Self {
+            timeouts: vec![0; seats.len()],
+            seats,
+            game,
+            dice,
+            rolls: Vec::new(),
+            turn_clock,
+        }
+    }
+
+    pub fn game(&self) -> &rules::GameState {
+        &self.game
+    }
+
+    pub fn dice(&self) -> &FairDice {
+        &self.dice
+    }
+
+    pub fn rolls(&self) -> &[u8] {
+        &self.rolls
+    }
+
+    pub fn turn_clock(&self) -> Option<TurnClock> {
+        self.turn_clock
+    }
+
+    pub fn seat_of(&self, client_id: u64) -> Option<usize> {
+        self.seats.iter().position(|&id| id == client_id)
+    }
+
+    /// Whether the player ran out of time too often in a row and is played by a bot.
+    pub fn is_afk(&self, seat: usize) -> bool {
+        match self.turn_clock {
+            Some(turn_clock) => {
+                self.timeouts.get(seat).copied().unwrap_or(0) >= turn_clock.timeouts_until_afk
+            }
+            None => false,
+        }
+    }
+
+    pub fn afk_seats(&self) -> Vec<usize> {
+        (0..self.seats.len())
+            .filter(|&seat| self.is_afk(seat))
+            .collect()
+    }
+
+    /// Gives the seat of a client to the client it reconnected as.
+    pub fn reseat(&mut self, old_id: u64, new_id: u64) {
+        for seat in self.seats.iter_mut().filter(|seat| **seat == old_id) {
+            *seat = new_id;
+        }
+    }
+
+    /// The dice seed, but only once the match is over.
+    pub fn revealed_seed(&self) -> Option<Seed> {
+        self.game.is_over().then(|| self.dice.seed())
+    }
+
+    pub fn handle_intent(
+        &mut self,
+        client_id: u64,
+        intent: Intent,
+    ) -> Result<(Action, Vec<Event>), IntentError> {
+        let seat = self.seat_of(client_id).ok_or(IntentError::NotSeated)?;
+        if seat != self.game.current_player() {
+            return Err(IntentError::NotYourTurn);
+        }
+        let result = self.apply(intent).map_err(IntentError::Rule)?;
+        // Acting again brings the player back from being away
+        if let Some(timeouts) = self.timeouts.get_mut(seat) {
+            *timeouts = 0;
+        }
+        Ok(result)
+    }
+
+    /// Makes the next move for a player that is away.
+    pub fn play_for_afk_player(&mut self) -> Option<(Action, Vec<Event>)> {
+        if !self.is_afk(self.game.current_player()) {
+            return None;
+        }
+        let decision = bot::decide(&self.game, Difficulty::Cautious, &mut rand::thread_rng())?;
+        self.apply(decision.into()).ok()
+    }
+
+    /// Ends the turn of a player that ran out of time, with as few moves as the rules allow:
+    /// only the mandatory rolls, each placed in the first free trough.
+    pub fn time_out(&mut self) -> Vec<(Action, Vec<Event>)> {
+        let seat = self.game.current_player();
+        if self.timeouts.len() < self.seats.len() {
+            self.timeouts.resize(self.seats.len(), 0);
+        }
+        self.timeouts[seat] += 1;
+
+        let turn = self.game.turn_number();
+        let mut applied = Vec::new();
+        while self.game.current_player() == seat && self.game.turn_number() == turn {
+            let intent = match self.game.phase() {
+                Phase::Finished => break,
+                Phase::Rolling if self.game.can_end_turn() => Intent::EndTurn,
+                Phase::Rolling => Intent::RollDice,
+                Phase::PlacingInGroup(group) => {
+                    let board = self.game.board();
+                    match board
+                        .troughs_in_group(group)
+                        .find(|&trough| !board.is_occupied(trough))
+                    {
+                        Some(trough) => Intent::Place(trough),
+                        None => break,
+                    }
+                }
+                Phase::CollectingGroup(group) => Intent::Collect(group),
+            };
+            match self.apply(intent) {
+                Ok(result) => applied.push(result),
+                Err(_) => break,
+            }
+        }
+        applied
+    }
+
+    fn apply(&mut self, intent: Intent) -> Result<(Action, Vec<Event>), RuleError> {
+        // Only use up a roll once it was accepted, so the n-th roll of the match
+        // is always the n-th roll of the seed.
+        let action = intent.into_action(|| self.dice.peek());
+        let events = self.game.apply(action)?;
+        if let Action::Roll(roll) = action {
+            self.dice.roll();
+            self.rolls.push(roll);
+        }
+        Ok((action, events))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TURN_CLOCK: TurnClock = TurnClock {
+        seconds: 10,
+        timeouts_until_afk: 2,
+    };
+
+    fn new_match() -> AuthoritativeMatch {
+        AuthoritativeMatch::new(vec![1, 2], RuleSet::default(), Some(TURN_CLOCK))
+    }
+
+    /// Lets every seat run out of time once.
+    fn time_out_round(authoritative_match: &mut AuthoritativeMatch) {
+        for _ in 0..2 {
+            authoritative_match.time_out();
+        }
+    }
+
+    #[test]
+    fn timing_out_makes_the_mandatory_rolls_into_first_free_troughs() {
+        let mut authoritative_match = new_match();
+        while let Some(mandatory_rolls) = authoritative_match.game().mandatory_rolls() {
+            let mut game = authoritative_match.game().clone();
+            let seat = game.current_player();
+            let applied = authoritative_match.time_out();
+            let rolls = applied
+                .iter()
+                .filter(|(action, _)| matches!(action, Action::Roll(_)))
+                .count();
+            // Collecting a group ends the turn early
+            if !applied
+                .iter()
+                .any(|(action, _)| matches!(action, Action::Collect(_)))
+            {
+                assert_eq!(rolls, mandatory_rolls);
+            }
+            assert_ne!(authoritative_match.game().current_player(), seat);
+            for (action, _) in applied {
+                if let (Phase::PlacingInGroup(group), Action::Place(trough)) =
+                    (game.phase(), action)
+                {
+                    let board = game.board();
+                    let first_free = board
+                        .troughs_in_group(group)
+                        .find(|&trough| !board.is_occupied(trough));
+                    assert_eq!(Some(trough), first_free);
+                }
+                game.apply(action).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn timing_out_after_the_opening_rolls_once() {
+        let mut authoritative_match = new_match();
+        while authoritative_match.game().mandatory_rolls().is_some() {
+            authoritative_match.time_out();
+        }
+        let seat = authoritative_match.game().current_player();
+        let applied = authoritative_match.time_out();
+        let rolls = applied
+            .iter()
+            .filter(|(action, _)| matches!(action, Action::Roll(_)))
+            .count();
+        assert_eq!(rolls, 1);
+        assert_ne!(authoritative_match.game().current_player(), seat);
+    }
+
+    #[test]
+    fn players_are_afk_after_timeouts_in_a_row() {
+        let mut authoritative_match = new_match();
+        time_out_round(&mut authoritative_match);
+        assert!(authoritative_match.afk_seats().is_empty());
+        authoritative_match.time_out();
+        assert!(authoritative_match.is_afk(0));
+        assert!(!authoritative_match.is_afk(1));
+        assert_eq!(authoritative_match.afk_seats(), vec![0]);
+    }
+
+    #[test]
+    fn acting_again_brings_a_player_back() {
+        let mut authoritative_match = new_match();
+        time_out_round(&mut authoritative_match);
+        time_out_round(&mut authoritative_match);
+        assert_eq!(authoritative_match.afk_seats(), vec![0, 1]);
+        authoritative_match
+            .handle_intent(1, Intent::RollDice)
+            .unwrap();
+        assert_eq!(authoritative_match.afk_seats(), vec![1]);
+    }
+
+    #[test]
+    fn acting_resets_the_timeouts_in_a_row() {
+        let mut authoritative_match = new_match();
+        time_out_round(&mut authoritative_match);
+        authoritative_match
+            .handle_intent(1, Intent::RollDice)
+            .unwrap();
+        while authoritative_match.game().current_player() == 0 {
+            authoritative_match.time_out();
+        }
+        authoritative_match.time_out();
+        assert!(!authoritative_match.is_afk(0));
+        assert!(authoritative_match.is_afk(1));
+    }
+
+    #[test]
+    fn bots_only_play_for_afk_players() {
+        let mut authoritative_match = new_match();
+        assert!(authoritative_match.play_for_afk_player().is_none());
+        time_out_round(&mut authoritative_match);
+        time_out_round(&mut authoritative_match);
+        assert!(authoritative_match.play_for_afk_player().is_some());
+    }
+
+    #[test]
+    fn nobody_is_afk_without_a_turn_clock() {
+        let mut authoritative_match = AuthoritativeMatch::new(vec![1, 2], RuleSet::default(), None);
+        time_out_round(&mut authoritative_match);
+        time_out_round(&mut authoritative_match);
+        assert!(authoritative_match.afk_seats().is_empty());
+    }
+}
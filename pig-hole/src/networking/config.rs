@@ -0,0 +1,48 @@
+use std::net::SocketAddr;
+
+/// Where the matchmaker is and how a hosted server can be reached.
+/// Every value can be overridden with an environment variable, e.g. `PIG_HOLE_PUBLIC_ADDRESS`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetworkConfig {
+    pub matchmaker_url: String,
+    /// Local address the server socket is bound to.
+    pub bind_address: SocketAddr,
+    /// Address clients use to reach the server, e.g. behind a port forwarding.
+    pub public_address: SocketAddr,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            matchmaker_url: "http://127.0.0.1:8000".to_string(),
+            bind_address: "127.0.0.1:1337".parse().unwrap(),
+            public_address: "127.0.0.1:1337".parse().unwrap(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            matchmaker_url: read_env("PIG_HOLE_MATCHMAKER_URL").unwrap_or(default.matchmaker_url),
+            bind_address: read_env("PIG_HOLE_BIND_ADDRESS").unwrap_or(default.bind_address),
+            public_address: read_env("PIG_HOLE_PUBLIC_ADDRESS").unwrap_or(default.public_address),
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.matchmaker_url.trim_end_matches('/'), path)
+    }
+}
+
+fn read_env<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            bevy::log::warn!("Ignoring invalid value of {}: {}", name, value);
+            None
+        }
+    }
+}
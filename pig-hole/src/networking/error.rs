@@ -0,0 +1,86 @@
+use super::http::HttpError;
+use std::fmt::Display;
+
+/// Everything that can go wrong while finding, hosting or playing a networked match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NetworkError {
+    /// The matchmaker could not be reached or answered unexpectedly.
+    Matchmaker(HttpError),
+    LobbyNameTaken,
+    /// Lobby names must not be empty, too long or contain spaces or any of `/:?#%`.
+    InvalidLobbyName,
+    LobbyNotFound,
+    /// The lobby has a password, and it was not the one given.
+    WrongPassword,
+    /// The account is already in the lobby, e.g. from another device.
+    UsernameTaken,
+    /// The lobby is full, or its match has started without this player.
+    LobbyClosed,
+    /// The lobby exists, but its host has not registered a server yet.
+    ServerNotReady,
+    /// The matchmaker did not accept the host token, e.g. because the lobby is hosted by someone else.
+    Unauthorized,
+    /// The session has expired, the player has to log in again.
+    SessionExpired,
+    /// Logging in failed, the username or the password is wrong.
+    WrongCredentials,
+    AccountTaken,
+    /// Usernames must not be empty, too long or start or end with spaces.
+    InvalidUsername,
+    /// No local socket could be opened.
+    Socket(String),
+    /// The connect token could not be used, e.g. because it has expired.
+    InvalidToken(String),
+    /// The connection was closed, e.g. because the lobby is full or the player was kicked.
+    Disconnected(String),
+    Transport(String),
+    /// The host sent rules or a game that cannot be played, e.g. because it is buggy.
+    InvalidMatch(String),
+}
+
+impl From<HttpError> for NetworkError {
+    fn from(error: HttpError) -> Self {
+        match error {
+            HttpError::Status(401) => NetworkError::Unauthorized,
+            HttpError::Status(403) => NetworkError::WrongPassword,
+            HttpError::Status(404) => NetworkError::LobbyNotFound,
+            HttpError::Status(409) => NetworkError::LobbyNameTaken,
+            HttpError::Status(423) => NetworkError::LobbyClosed,
+            HttpError::Status(503) => NetworkError::ServerNotReady,
+            error => NetworkError::Matchmaker(error),
+        }
+    }
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::Matchmaker(error) => write!(f, "{}", error),
+            NetworkError::LobbyNameTaken => write!(f, "A lobby with this name already exists"),
+            NetworkError::InvalidLobbyName => {
+                write!(f, "Lobby names must be short, without spaces or / : ? # %")
+            }
+            NetworkError::LobbyNotFound => write!(f, "The lobby does not exist anymore"),
+            NetworkError::WrongPassword => write!(f, "The password is wrong"),
+            NetworkError::UsernameTaken => write!(f, "This account is already in the lobby"),
+            NetworkError::LobbyClosed => write!(f, "The lobby is full or already playing"),
+            NetworkError::ServerNotReady => write!(f, "The host is not ready yet"),
+            NetworkError::Unauthorized => write!(f, "The matchmaker did not accept this host"),
+            NetworkError::SessionExpired => {
+                write!(f, "The session has expired, please log in again")
+            }
+            NetworkError::WrongCredentials => write!(f, "The username or the password is wrong"),
+            NetworkError::AccountTaken => write!(f, "An account with this name already exists"),
+            NetworkError::InvalidUsername => write!(f, "This username is not allowed"),
+            NetworkError::Socket(reason) => write!(f, "Could not open a connection: {}", reason),
+            NetworkError::InvalidToken(reason) => write!(f, "Invalid connect token: {}", reason),
+            NetworkError::Disconnected(reason) => write!(f, "Disconnected: {}", reason),
+            NetworkError::Transport(reason) => write!(f, "Connection error: {}", reason),
+            NetworkError::InvalidMatch(reason) => {
+                write!(f, "The host sent a match that cannot be played: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
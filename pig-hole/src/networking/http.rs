@@ -1,5 +1,6 @@
 use bevy::log;
 use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Display;
 
 #[cfg(target_arch = "wasm32")]
 use js_sys::Promise;
@@ -12,21 +13,75 @@ use wasm_bindgen_futures::JsFuture;
 #[cfg(target_arch = "wasm32")]
 use web_sys::{Request, RequestInit, Response};
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HttpError {
+    /// The server could not be reached at all.
+    Unreachable(String),
+    /// The server answered with a status code other than 2xx.
+    Status(u16),
+    /// The server answered with something that could not be read.
+    InvalidResponse(String),
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpError::Unreachable(reason) => write!(f, "Could not reach the server: {}", reason),
+            HttpError::Status(code) => write!(f, "The server answered with status {}", code),
+            HttpError::InvalidResponse(reason) => {
+                write!(f, "The server sent an invalid response: {}", reason)
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<ureq::Error> for HttpError {
+    fn from(error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::Status(code, _) => HttpError::Status(code),
+            ureq::Error::Transport(transport) => HttpError::Unreachable(transport.to_string()),
+        }
+    }
+}
+
 // Source: https://github.com/vleue/jornet/blob/2a414a8f85f975ae8d54b9e3ceab348db7c6250d/bevy-jornet/src/http.rs#L12-L25
 
 pub async fn put<TBody: Serialize, TResponse: DeserializeOwned>(
     url: &str,
     body: TBody,
-) -> TResponse {
+) -> Result<TResponse, HttpError> {
+    put_as(url, None, body).await
+}
+
+/// Like `put`, but sends the session token of the logged in user along.
+pub async fn put_as<TBody: Serialize, TResponse: DeserializeOwned>(
+    url: &str,
+    token: Option<&str>,
+    body: TBody,
+) -> Result<TResponse, HttpError> {
+    let authorization = token.map(|token| format!("Bearer {}", token));
     #[cfg(not(target_arch = "wasm32"))]
-    let result = ureq::put(url)
-        .send_json(&body)
-        .unwrap()
-        .into_json()
-        .unwrap();
+    let result = {
+        let mut request = ureq::put(url);
+        if let Some(authorization) = &authorization {
+            request = request.set("Authorization", authorization);
+        }
+        request
+            .send_json(&body)
+            .map_err(HttpError::from)
+            .and_then(|response| {
+                response
+                    .into_json()
+                    .map_err(|error| HttpError::InvalidResponse(error.to_string()))
+            })
+    };
     #[cfg(target_arch = "wasm32")]
-    let result = request(url, body, "PUT").await;
+    let result = request(url, body, "PUT", authorization.as_deref()).await;
 
+    if let Err(error) = &result {
+        log::warn!("PUT {} failed: {}", url, error);
+    }
     result
 }
 
@@ -35,38 +90,62 @@ async fn request<TBody: Serialize, TResponse: DeserializeOwned>(
     url: &str,
     body: TBody,
     method: &str,
-) -> TResponse {
+    authorization: Option<&str>,
+) -> Result<TResponse, HttpError> {
     let mut headers = HashMap::new();
     headers.insert("Content-Type", "application/json");
+    if let Some(authorization) = authorization {
+        headers.insert("Authorization", authorization);
+    }
     let mut opts = RequestInit::new();
     let json = serde_json::to_string(&body).unwrap();
-    log::info!("{} {} {}", method, url, json);
+    log::info!("{} {}", method, url);
     opts.method(method)
         .body(Some(&JsValue::from_str(&json)))
         .headers(&JsValue::from_serde(&headers).unwrap());
 
-    let request = Request::new_with_str_and_init(&url, &opts).unwrap();
+    let request = Request::new_with_str_and_init(&url, &opts)
+        .map_err(|error| HttpError::Unreachable(format!("{:?}", error)))?;
 
     let window = web_sys::window().unwrap();
     await_promise(window.fetch_with_request(&request)).await
 }
 
 #[cfg(target_arch = "wasm32")]
-async fn await_promise<T: DeserializeOwned>(promise: Promise) -> T {
-    let resp_value = JsFuture::from(promise).await.unwrap();
+async fn await_promise<T: DeserializeOwned>(promise: Promise) -> Result<T, HttpError> {
+    let resp_value = JsFuture::from(promise)
+        .await
+        .map_err(|error| HttpError::Unreachable(format!("{:?}", error)))?;
     let resp: Response = resp_value.dyn_into().unwrap();
-    let val = JsFuture::from(resp.json().unwrap()).await.unwrap();
-    val.into_serde().unwrap()
+    if !resp.ok() {
+        return Err(HttpError::Status(resp.status()));
+    }
+    let invalid_response = |error| HttpError::InvalidResponse(format!("{:?}", error));
+    let val = JsFuture::from(resp.json().map_err(invalid_response)?)
+        .await
+        .map_err(invalid_response)?;
+    val.into_serde()
+        .map_err(|error| HttpError::InvalidResponse(error.to_string()))
 }
 
-pub async fn get<T: DeserializeOwned>(url: &str) -> T {
+pub async fn get<T: DeserializeOwned>(url: &str) -> Result<T, HttpError> {
     #[cfg(not(target_arch = "wasm32"))]
-    let result = ureq::get(url).call().unwrap().into_json().unwrap();
+    let result = ureq::get(url)
+        .call()
+        .map_err(HttpError::from)
+        .and_then(|response| {
+            response
+                .into_json()
+                .map_err(|error| HttpError::InvalidResponse(error.to_string()))
+        });
     #[cfg(target_arch = "wasm32")]
     let result = {
         let window = web_sys::window().unwrap();
         await_promise(window.fetch_with_str(url)).await
     };
 
+    if let Err(error) = &result {
+        log::warn!("GET {} failed: {}", url, error);
+    }
     result
 }
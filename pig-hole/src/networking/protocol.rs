@@ -0,0 +1,93 @@
+use crate::player::Intent;
+use rules::dice::{Commitment, Seed};
+use rules::{Action, Event, RuleSet};
+use serde::{Deserialize, Serialize};
+
+/// Channel for everything that must arrive, in order.
+pub const RELIABLE_CHANNEL: u8 = 0;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    pub id: u64,
+    /// The account the player joined with, which gets the seat back after a reconnect.
+    #[serde(default)]
+    pub user_id: u64,
+    pub username: String,
+}
+
+/// How long players may take for their turn, set by the host of the lobby.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct TurnClock {
+    pub seconds: u32,
+    /// Timeouts in a row after which a player counts as away and is played by a bot.
+    pub timeouts_until_afk: u32,
+}
+
+impl Default for TurnClock {
+    fn default() -> Self {
+        Self {
+            seconds: 60,
+            timeouts_until_afk: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ClientMessages {
+    Intent(Intent),
+    /// Asks the server for the complete game state, e.g. after a desync.
+    RequestSync,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ServerMessages {
+    PlayerConnected {
+        player: LobbyPlayer,
+    },
+    PlayerDisconnected {
+        id: u64,
+    },
+    /// The match has started with the given players, in turn order.
+    /// The dice commitment is the hash of the seed all rolls will be derived from.
+    MatchStarted {
+        seats: Vec<LobbyPlayer>,
+        dice_commitment: Commitment,
+        rules: RuleSet,
+    },
+    /// An intent was accepted. Applying the action to the own game state
+    /// must produce the same events, otherwise the client is out of sync.
+    ActionApplied {
+        action: Action,
+        events: Vec<Event>,
+    },
+    IntentRejected {
+        reason: String,
+    },
+    Sync {
+        game: rules::GameState,
+    },
+    /// Sent to a client that reconnected to a running match.
+    Rejoined {
+        seats: Vec<LobbyPlayer>,
+        game: rules::GameState,
+        dice_commitment: Commitment,
+        rolls: Vec<u8>,
+        /// Seconds the player on turn has left, if the turn clock runs.
+        seconds_left: Option<u32>,
+        /// Seats that are played by a bot until their players are back.
+        afk_seats: Vec<usize>,
+    },
+    /// Sent once the match is over, so that every client can verify the rolls.
+    SeedRevealed {
+        seed: Seed,
+    },
+    /// The player on turn has the given time before the host ends the turn for them.
+    TurnClockStarted {
+        seconds: u32,
+    },
+    /// A player is away and played by a bot, or is back.
+    AfkChanged {
+        seat: usize,
+        afk: bool,
+    },
+}
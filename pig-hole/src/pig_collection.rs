@@ -62,7 +62,10 @@ fn spawn_nth_pig(
         .id()
 }
 
-fn get_relative_position_of_nth_pig(n: u32, board_asset_creator: &Res<BoardAssetCreator>) -> Vec3 {
+pub fn get_relative_position_of_nth_pig(
+    n: u32,
+    board_asset_creator: &Res<BoardAssetCreator>,
+) -> Vec3 {
     let padding = board_asset_creator.get_pig_collection_padding();
     let aabb = board_asset_creator.get_pig_aabb();
     let x_index = (n as f32 / 2.0).floor();
@@ -1,16 +1,22 @@
 use crate::actions::Actions;
+use crate::animation::{AnimationFinished, Animations, StartAnimations};
 use crate::board::Pig;
 use crate::board::PigStatus;
+use crate::handover::Handover;
 use crate::ingame_menu::InteractionModel;
+use crate::networking::run_if_offline;
 use crate::pig_collection::PigCollection;
-use crate::turn::Turn;
-use crate::GameState;
+use crate::turn::{IsOnTurn, Turn, TurnChangeEvent};
+use crate::{GameState, MatchEntity};
 use bevy::prelude::*;
 #[cfg(feature = "dev")]
 use bevy_inspector_egui::Inspectable;
 #[cfg(feature = "dev")]
 use bevy_inspector_egui::RegisterInspectable;
-use rand::Rng;
+use rules::bot::Decision;
+use rules::game::STARTING_PIGS;
+use rules::{Action, History, Phase, ReplayEvent};
+use serde::{Deserialize, Serialize};
 
 pub struct PlayerPlugin;
 
@@ -18,6 +24,8 @@ pub struct PlayerPlugin;
 #[cfg_attr(feature = "dev", derive(Inspectable))]
 pub struct Player {
     pub state: PlayerState,
+    /// Seat of the player in the [`rules::GameState`].
+    pub index: usize,
     pub pig_count: u32,
     pub action_count: usize,
 }
@@ -26,7 +34,8 @@ impl Default for Player {
     fn default() -> Self {
         Player {
             state: PlayerState::Thinking(),
-            pig_count: 20,
+            index: 0,
+            pig_count: STARTING_PIGS,
             action_count: 0,
         }
     }
@@ -53,6 +62,43 @@ pub struct PlayerInteractionModel {
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct OuterTroughIndex(u8);
 
+/// Marks players whose input comes from this machine.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Component)]
+pub struct LocalPlayer;
+
+/// What a local player wants to do on their turn.
+/// Unlike [`Action`], a dice roll does not carry its result,
+/// since the roll is made by whoever runs the rules.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Intent {
+    RollDice,
+    Place(rules::Trough),
+    Collect(u8),
+    EndTurn,
+}
+
+impl Intent {
+    pub fn into_action(self, roll_dice: impl FnOnce() -> u8) -> Action {
+        match self {
+            Intent::RollDice => Action::Roll(roll_dice()),
+            Intent::Place(trough) => Action::Place(trough),
+            Intent::Collect(group) => Action::Collect(group),
+            Intent::EndTurn => Action::EndTurn,
+        }
+    }
+}
+
+impl From<Decision> for Intent {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Roll => Intent::RollDice,
+            Decision::Place(trough) => Intent::Place(trough),
+            Decision::Collect(group) => Intent::Collect(group),
+            Decision::EndTurn => Intent::EndTurn,
+        }
+    }
+}
+
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for PlayerPlugin {
@@ -61,11 +107,15 @@ impl Plugin for PlayerPlugin {
             .add_system_set(
                 SystemSet::on_update(GameState::Playing)
                     .with_system(select_pig)
-                    .with_system(throw_dice)
                     .with_system(sync_interaction_model)
-                    .with_system(resume_turn)
-                    .with_system(check_win_condition),
+                    .with_system(sync_with_game.after(StartAnimations)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_run_criteria(run_if_offline)
+                    .with_system(apply_intents),
             )
+            .add_event::<Intent>()
             .init_resource::<PlayerInteractionModel>();
 
         #[cfg(feature = "dev")]
@@ -80,39 +130,29 @@ fn spawn_camera(mut commands: Commands) {
     commands
         .spawn_bundle(OrthographicCameraBundle::new_2d())
         .insert(Transform::from_xyz(250.0, 0.0, 999.9))
-        .insert(Name::new("Camera"));
+        .insert(Name::new("Camera"))
+        .insert(MatchEntity);
 }
 
 fn select_pig(
     mut pig_query: Query<&mut Pig>,
     actions: Res<Actions>,
-    mut player_query: Query<&mut Player>,
-    mut pig_collection_query: Query<&mut PigCollection>,
-    mut turn: ResMut<Turn>,
+    mut player_query: Query<&mut Player, (With<IsOnTurn>, With<LocalPlayer>)>,
+    mut intents: EventWriter<Intent>,
+    handover: Res<Handover>,
 ) {
+    if handover.is_pending() {
+        return;
+    }
     for mut player in player_query.iter_mut() {
         match player.state {
             PlayerState::PlacingInGroup(group) => {
                 if let Some(selected_pig) = actions.selected_pig {
-                    if let Some(mut pig) = find_mut_pig(&selected_pig, &mut pig_query) {
+                    if let Some(pig) = find_mut_pig(&selected_pig, &mut pig_query) {
                         if is_valid_for_placement(&pig, group) {
-                            pig.status = PigStatus::Occupied;
-                            player.action_count += 1;
-                            match turn.get_min_actions() {
-                                Some(min_actions) => {
-                                    if player.action_count < min_actions {
-                                        player.state = PlayerState::Thinking()
-                                    } else {
-                                        end_turn(&mut player, &mut turn)
-                                    }
-                                }
-                                None => player.state = PlayerState::Thinking(),
-                            };
-                            player.pig_count = player.pig_count.saturating_sub(1);
-                            for mut pig_collection in pig_collection_query.iter_mut() {
-                                pig_collection.modify_by -= 1;
-                            }
-                            clear_ghosts(&mut pig_query);
+                            intents.send(Intent::Place(pig.trough.into()));
+                            // Ignore further input until the game has processed the placement
+                            player.state = PlayerState::Waiting();
                         }
                     }
                 } else if let Some(hovered_pig) = actions.hovered_trough {
@@ -131,25 +171,12 @@ fn select_pig(
                     if selected_pig.trough.group != group {
                         return;
                     }
-                    for mut pig in pig_query.iter_mut().filter(|pig| pig.trough.group == group) {
-                        pig.status = PigStatus::Empty;
-                        end_turn(&mut player, &mut turn);
-                    }
-
-                    player.pig_count = player.pig_count.saturating_add(group as u32);
-
-                    for mut pig_collection in pig_collection_query.iter_mut() {
-                        pig_collection.modify_by += group as i32;
-                    }
+                    intents.send(Intent::Collect(group));
+                    player.state = PlayerState::Waiting();
                 } else if let Some(hovered_pig) = actions.hovered_trough {
                     if hovered_pig.trough.group != group {
                         return;
                     }
-                    if let Some(mut pig) = find_mut_pig(&hovered_pig, &mut pig_query) {
-                        if is_valid_for_placement(&pig, group) {
-                            pig.status = PigStatus::PlacementGhost;
-                        }
-                    }
                     for mut pig in pig_query.iter_mut() {
                         if pig.trough.group == group {
                             pig.status = PigStatus::RemovalGhost;
@@ -164,36 +191,19 @@ fn select_pig(
     }
 }
 
-fn end_turn(player: &mut Mut<Player>, turn: &mut ResMut<Turn>) {
-    player.action_count = 0;
-    turn.start_next_players_turn();
-    player.state = PlayerState::Waiting();
-}
-
-fn throw_dice(mut player_query: Query<&mut Player>, pig_query: Query<&Pig>) {
-    for mut player in player_query.iter_mut() {
-        match player.state {
-            PlayerState::ThrowingDice() => {
-                let mut rng = rand::thread_rng();
-                let roll = rng.gen_range(1..=6);
-                player.state = if is_group_full(&pig_query, roll) {
-                    PlayerState::CollectingGroup(roll)
-                } else {
-                    PlayerState::PlacingInGroup(roll)
-                };
-            }
-            _ => (),
-        }
-    }
-}
-
-fn is_group_full(pig_query: &Query<&Pig>, group: u8) -> bool {
-    for pig in pig_query.iter() {
-        if !pig.is_occupied() && pig.trough.group == group {
-            return false;
+fn apply_intents(
+    mut intents: EventReader<Intent>,
+    mut game: ResMut<rules::GameState>,
+    mut history: ResMut<History>,
+    mut replay_events: EventWriter<ReplayEvent>,
+) {
+    for intent in intents.iter() {
+        let action = intent.into_action(|| game.rules().roll(&mut rand::thread_rng()));
+        match history.apply(&mut game, action) {
+            Ok(_) => replay_events.send(ReplayEvent::Action(action)),
+            Err(error) => warn!("Rejected {:?}: {}", action, error),
         }
     }
-    true
 }
 
 fn find_mut_pig<'a>(needle: &Pig, haystack: &'a mut Query<&mut Pig>) -> Option<Mut<'a, Pig>> {
@@ -225,25 +235,34 @@ fn clear_ghosts_except(pig_query: &mut Query<&mut Pig>, exception: &Pig) {
 
 fn sync_interaction_model(
     mut interaction_model: ResMut<PlayerInteractionModel>,
-    mut player: Query<&mut Player>,
-    mut turn: ResMut<Turn>,
+    mut player: Query<&mut Player, (With<IsOnTurn>, With<LocalPlayer>)>,
+    mut intents: EventWriter<Intent>,
+    game: Res<rules::GameState>,
+    handover: Res<Handover>,
 ) {
+    if handover.is_pending() {
+        interaction_model.roll_dice.deny();
+        interaction_model.end_turn.deny();
+        return;
+    }
     for mut player in player.iter_mut() {
         if interaction_model.roll_dice.get_interaction().is_some() {
             if player.state == PlayerState::Thinking() {
+                intents.send(Intent::RollDice);
                 player.state = PlayerState::ThrowingDice()
             }
         }
         if interaction_model.end_turn.get_interaction().is_some() {
             if player.state == PlayerState::Thinking() {
-                end_turn(&mut player, &mut turn);
+                intents.send(Intent::EndTurn);
+                player.state = PlayerState::Waiting();
             }
         }
 
         match player.state {
             PlayerState::Thinking() => {
                 interaction_model.roll_dice.allow();
-                if turn.get_min_actions().is_none() && player.action_count > 0 {
+                if game.can_end_turn() {
                     interaction_model.end_turn.allow();
                 } else {
                     interaction_model.end_turn.deny();
@@ -257,28 +276,57 @@ fn sync_interaction_model(
     }
 }
 
-fn resume_turn(mut player_query: Query<&mut Player>, turn: Res<Turn>) {
-    let mut player = player_query
-        .get_mut(turn.get_current_player())
-        .expect("No current player found");
-    if player.state == PlayerState::Waiting() {
-        player.state = PlayerState::Thinking();
+/// Mirrors the authoritative [`rules::GameState`] into the components used for rendering.
+/// Waits for the animations of the last change, so players only continue once they are done.
+#[allow(clippy::too_many_arguments)]
+fn sync_with_game(
+    game: Res<rules::GameState>,
+    animations: Res<Animations>,
+    mut animation_finished_events: EventReader<AnimationFinished>,
+    mut turn: ResMut<Turn>,
+    mut player_query: Query<(Entity, &mut Player)>,
+    mut pig_query: Query<&mut Pig>,
+    mut pig_collection_query: Query<(&Parent, &mut PigCollection)>,
+    mut turn_change_events: EventWriter<TurnChangeEvent>,
+) {
+    let animation_finished = animation_finished_events.iter().count() > 0;
+    if !(game.is_changed() || animation_finished) || animations.is_running() {
+        return;
     }
-}
-
-fn check_win_condition(mut player_query: Query<&mut Player>) {
-    let mut game_ended = false;
-    for mut player in player_query.iter_mut() {
-        if player.pig_count == 0 {
-            player.state = PlayerState::Won();
-            game_ended = true;
-        }
+    for mut pig in pig_query.iter_mut() {
+        pig.status = if game.board().is_occupied(pig.trough.into()) {
+            PigStatus::Occupied
+        } else {
+            PigStatus::Empty
+        };
     }
-    if game_ended {
-        for mut player in player_query.iter_mut() {
-            if player.state != PlayerState::Won() {
-                player.state = PlayerState::Lost();
+    for (entity, mut player) in player_query.iter_mut() {
+        player.state = get_player_state(&game, player.index);
+        player.pig_count = game.pig_count(player.index);
+        player.action_count = if game.current_player() == player.index {
+            game.action_count()
+        } else {
+            0
+        };
+        for (parent, mut pig_collection) in pig_collection_query.iter_mut() {
+            if parent.0 == entity {
+                pig_collection.modify_by =
+                    player.pig_count as i32 - pig_collection.pigs.len() as i32;
             }
         }
     }
+    if turn.sync_with(&game) {
+        turn_change_events.send(TurnChangeEvent);
+    }
+}
+
+fn get_player_state(game: &rules::GameState, index: usize) -> PlayerState {
+    match game.phase() {
+        Phase::Finished if game.winner() == Some(index) => PlayerState::Won(),
+        Phase::Finished => PlayerState::Lost(),
+        _ if game.current_player() != index => PlayerState::Waiting(),
+        Phase::Rolling => PlayerState::Thinking(),
+        Phase::PlacingInGroup(group) => PlayerState::PlacingInGroup(group),
+        Phase::CollectingGroup(group) => PlayerState::CollectingGroup(group),
+    }
 }
@@ -1,37 +1,173 @@
 use bevy::prelude::*;
+use rules::bot::Difficulty;
+use rules::RuleSet;
+use serde::{Deserialize, Serialize};
 
-use crate::{pig_collection::PigCollection, player::Player, turn::Turn, GameState};
+use crate::{
+    bot::Bot,
+    loading::FontAssets,
+    networking::TurnClockState,
+    pig_collection::PigCollection,
+    player::{LocalPlayer, Player},
+    turn::{IsOnTurn, Turn},
+    GameState, MatchEntity,
+};
 
 pub struct PlayerCreationPlugin;
 
 impl Plugin for PlayerCreationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_players));
+        app.init_resource::<Seats>()
+            .init_resource::<RuleSet>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_players))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(highlight_current_player),
+            );
     }
 }
 
-fn spawn_players(mut commands: Commands) {
-    let player_order = (0..1).map(|_| spawn_player(&mut commands)).collect();
+/// The players taking part in the next match, in turn order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Seats(pub Vec<Seat>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Seat {
+    pub name: String,
+    pub controller: Controller,
+}
+
+/// Who decides the moves of a seat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Controller {
+    /// A human on this machine.
+    Local,
+    /// A player on another machine, whose moves arrive over the network.
+    Remote,
+    /// A computer opponent played by this machine.
+    Bot(Difficulty),
+}
+
+impl Default for Seats {
+    fn default() -> Self {
+        Self(vec![Seat {
+            name: "Player".to_string(),
+            controller: Controller::Local,
+        }])
+    }
+}
+
+/// A match that continues where it was left instead of starting over.
+/// Consumed when the players are spawned.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResumedGame(pub rules::GameState);
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Component)]
+struct PlayerNameLabel;
+
+const ROW_HEIGHT: f32 = 70.0;
+const ROW_SCALE: f32 = 0.5;
+
+fn spawn_players(
+    mut commands: Commands,
+    seats: Res<Seats>,
+    rules: Res<RuleSet>,
+    resumed_game: Option<Res<ResumedGame>>,
+    font_assets: Res<FontAssets>,
+) {
+    let player_order: Vec<_> = seats
+        .0
+        .iter()
+        .enumerate()
+        .map(|(index, seat)| spawn_player(&mut commands, &font_assets, index, seat))
+        .collect();
+    commands.entity(player_order[0]).insert(IsOnTurn);
+    match resumed_game {
+        Some(resumed_game) => {
+            commands.insert_resource(resumed_game.0.clone());
+            commands.remove_resource::<ResumedGame>();
+        }
+        None => commands.insert_resource(rules::GameState::with_rules(
+            player_order.len(),
+            rules.clone(),
+        )),
+    }
     commands.insert_resource(Turn::new(player_order));
 }
 
-fn spawn_player(commands: &mut Commands) -> Entity {
-    commands
-        .spawn()
-        .insert(Player::default())
-        .insert(Name::new("Player"))
+fn spawn_player(
+    commands: &mut Commands,
+    font_assets: &Res<FontAssets>,
+    index: usize,
+    seat: &Seat,
+) -> Entity {
+    let mut player = commands.spawn();
+    match seat.controller {
+        Controller::Local => {
+            player.insert(LocalPlayer);
+        }
+        Controller::Bot(difficulty) => {
+            player.insert(Bot(difficulty));
+        }
+        Controller::Remote => (),
+    }
+    player
+        .insert(Player { index, ..default() })
+        .insert(MatchEntity)
+        .insert(Name::new(seat.name.clone()))
         .insert(GlobalTransform::default())
-        .insert(Transform::from_xyz(0.0, -230.0, 0.0))
+        .insert(Transform::from_xyz(
+            220.0,
+            330.0 - index as f32 * ROW_HEIGHT,
+            0.0,
+        ))
         .with_children(|parent| {
+            parent
+                .spawn_bundle(Text2dBundle {
+                    text: Text::with_section(
+                        seat.name.clone(),
+                        TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: 20.0,
+                            color: Color::GRAY,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Left,
+                        },
+                    ),
+                    ..default()
+                })
+                .insert(PlayerNameLabel);
             parent
                 .spawn()
                 .insert(Name::new("Pig collection"))
                 .insert(GlobalTransform::default())
-                .insert(Transform::from_xyz(-175., 0.0, 0.0))
-                .insert(PigCollection {
-                    modify_by: 20,
-                    ..default()
-                });
+                .insert(Transform::from_xyz(10.0, -30.0, 0.0).with_scale(Vec3::splat(ROW_SCALE)))
+                .insert(PigCollection::default());
         })
         .id()
 }
+
+fn highlight_current_player(
+    mut label_query: Query<(&Parent, &mut Text), With<PlayerNameLabel>>,
+    player_query: Query<(&Player, &Name, Option<&IsOnTurn>)>,
+    turn_clock: Res<TurnClockState>,
+) {
+    for (parent, mut text) in label_query.iter_mut() {
+        let (player, name, is_on_turn) = match player_query.get(parent.0) {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        let section = &mut text.sections[0];
+        section.style.color = if is_on_turn.is_some() {
+            Color::GOLD
+        } else {
+            Color::GRAY
+        };
+        section.value = if turn_clock.is_afk(player.index) {
+            format!("{} (away)", name.as_str())
+        } else {
+            name.as_str().to_string()
+        };
+    }
+}
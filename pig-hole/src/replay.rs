@@ -0,0 +1,458 @@
+use crate::player_creation::{Controller, ResumedGame, Seat, Seats};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use rules::replay::ReplayError;
+use rules::{Replay, ReplayEvent, RuleSet};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+
+/// Increased whenever replays of older versions can no longer be read.
+pub const REPLAY_VERSION: u32 = 1;
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 8.0;
+
+pub struct ReplayPlugin;
+
+/// This plugin records every match this machine takes part in, and plays recorded matches back.
+/// Playback shows the board like a match in which nobody on this machine has a seat.
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReplayEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_recording))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(record_events)
+                    .with_system(play_back)
+                    .with_system(show_playback_controls),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(finish_recording)
+                    .with_system(stop_playback),
+            );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct ReplayFile {
+    version: u32,
+    replay: Replay,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReplayFileError {
+    Storage(String),
+    Format(String),
+    UnsupportedVersion(u32),
+    /// The replay was read, but could not be shown.
+    Playback(String),
+}
+
+impl Display for ReplayFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayFileError::Storage(error) => write!(f, "Failed to access the replay: {}", error),
+            ReplayFileError::Format(error) => write!(f, "The replay is damaged: {}", error),
+            ReplayFileError::UnsupportedVersion(version) => write!(
+                f,
+                "The replay is from version {}, but only version {} is supported",
+                version, REPLAY_VERSION
+            ),
+            ReplayFileError::Playback(error) => write!(f, "Failed to play the replay: {}", error),
+        }
+    }
+}
+
+impl Error for ReplayFileError {}
+
+fn encode(replay: Replay) -> Result<Vec<u8>, ReplayFileError> {
+    let file = ReplayFile {
+        version: REPLAY_VERSION,
+        replay,
+    };
+    bincode::serialize(&file).map_err(|error| ReplayFileError::Format(error.to_string()))
+}
+
+fn decode(bytes: &[u8]) -> Result<Replay, ReplayFileError> {
+    let format_error = |error: bincode::Error| ReplayFileError::Format(error.to_string());
+    // The version comes first, so it can be read without knowing the rest of the format
+    let version: u32 = bincode::deserialize(bytes).map_err(format_error)?;
+    if version != REPLAY_VERSION {
+        return Err(ReplayFileError::UnsupportedVersion(version));
+    }
+    let file: ReplayFile = bincode::deserialize(bytes).map_err(format_error)?;
+    validate(&file.replay)?;
+    Ok(file.replay)
+}
+
+/// Damaged or edited replays must not make it to the board, where they would crash the game.
+fn validate(replay: &Replay) -> Result<(), ReplayFileError> {
+    let invalid = |reason: String| Err(ReplayFileError::Format(reason));
+    if let Err(error) = replay.rules.validate() {
+        return invalid(error.to_string());
+    }
+    if replay.players.is_empty() {
+        return invalid("The replay has no players".to_string());
+    }
+    for entry in replay.entries.iter() {
+        if let ReplayEvent::Synced(game) = &entry.event {
+            if let Err(error) = game.rules().validate() {
+                return invalid(error.to_string());
+            }
+            if game.player_count() != replay.players.len() {
+                return invalid(format!(
+                    "A game of {} players does not fit the {} players of the replay",
+                    game.player_count(),
+                    replay.players.len()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Names of the stored replays, newest first.
+pub fn list_replays() -> Result<Vec<String>, ReplayFileError> {
+    storage::list()
+}
+
+pub fn load_replay(name: &str) -> Result<Replay, ReplayFileError> {
+    decode(&storage::read(name)?)
+}
+
+/// The match being recorded on this machine.
+struct ReplayRecorder {
+    replay: Replay,
+    /// Seconds since startup when the recording started.
+    started: f64,
+}
+
+fn start_recording(
+    mut commands: Commands,
+    time: Res<Time>,
+    seats: Res<Seats>,
+    rules: Res<RuleSet>,
+    resumed_game: Option<Res<ResumedGame>>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    if playback.is_some() {
+        return;
+    }
+    let players = seats.0.iter().map(|seat| seat.name.clone()).collect();
+    let mut replay = Replay::new(rules.clone(), players);
+    if let Some(resumed_game) = resumed_game {
+        replay.record(0, ReplayEvent::Synced(resumed_game.0.clone()));
+    }
+    commands.insert_resource(ReplayRecorder {
+        replay,
+        started: time.seconds_since_startup(),
+    });
+}
+
+fn record_events(
+    time: Res<Time>,
+    mut events: EventReader<ReplayEvent>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+) {
+    let mut recorder = match recorder {
+        Some(recorder) => recorder,
+        None => return,
+    };
+    let elapsed = ((time.seconds_since_startup() - recorder.started) * 1000.0) as u64;
+    for event in events.iter() {
+        recorder.replay.record(elapsed, event.clone());
+    }
+}
+
+fn finish_recording(mut commands: Commands, recorder: Option<Res<ReplayRecorder>>) {
+    let recorder = match recorder {
+        Some(recorder) => recorder,
+        None => return,
+    };
+    commands.remove_resource::<ReplayRecorder>();
+    if recorder.replay.entries.is_empty() {
+        return;
+    }
+    let result = encode(recorder.replay.clone()).and_then(|bytes| storage::write(&bytes));
+    match result {
+        Ok(name) => info!("Saved the replay {}", name),
+        Err(error) => warn!("Failed to save the replay: {}", error),
+    }
+}
+
+/// A recorded match that is being watched.
+pub struct ReplayPlayback {
+    replay: Replay,
+    /// Milliseconds into the replay.
+    position: f64,
+    playing: bool,
+    speed: f32,
+    /// How many entries the shown game is built from, `None` until it was built.
+    shown_entries: Option<usize>,
+    /// How many entries to show after stepping, until the position is changed otherwise.
+    /// Entries recorded at the same time can only be told apart like this.
+    stepped_entries: Option<usize>,
+    error: Option<ReplayError>,
+}
+
+/// Shows the replay in place of a match. Nobody gets a seat, so there is no input to the game.
+pub fn start_playback(
+    commands: &mut Commands,
+    state: &mut State<GameState>,
+    replay: Replay,
+) -> Result<(), ReplayFileError> {
+    state
+        .set(GameState::Playing)
+        .map_err(|error| ReplayFileError::Playback(format!("{:?}", error)))?;
+    let seats = replay
+        .players
+        .iter()
+        .map(|name| Seat {
+            name: name.clone(),
+            controller: Controller::Remote,
+        })
+        .collect();
+    commands.insert_resource(Seats(seats));
+    commands.insert_resource(replay.rules.clone());
+    commands.insert_resource(ReplayPlayback {
+        replay,
+        position: 0.0,
+        playing: true,
+        speed: 1.0,
+        shown_entries: None,
+        stepped_entries: None,
+        error: None,
+    });
+    Ok(())
+}
+
+/// Milliseconds into the replay when the given number of entries is reached.
+fn time_of_entries(replay: &Replay, entries: usize) -> f64 {
+    match entries.checked_sub(1) {
+        Some(index) => replay.entries[index].time as f64,
+        None => 0.0,
+    }
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+fn play_back(
+    time: Res<Time>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    game: Option<ResMut<rules::GameState>>,
+) {
+    let (mut playback, mut game) = match (playback, game) {
+        (Some(playback), Some(game)) => (playback, game),
+        _ => return,
+    };
+    let duration = playback.replay.duration() as f64;
+    if playback.playing {
+        playback.stepped_entries = None;
+        playback.position += time.delta_seconds_f64() * 1000.0 * f64::from(playback.speed);
+        if playback.position >= duration {
+            playback.position = duration;
+            playback.playing = false;
+        }
+    }
+    let entries = match playback.stepped_entries {
+        Some(entries) if time_of_entries(&playback.replay, entries) == playback.position => entries,
+        _ => playback.replay.entries_until(playback.position as u64),
+    };
+    if playback.shown_entries == Some(entries) {
+        return;
+    }
+    playback.shown_entries = Some(entries);
+    match playback.replay.game_after(entries) {
+        Ok(shown) => {
+            *game = shown;
+            playback.error = None;
+        }
+        Err(error) => playback.error = Some(error),
+    }
+}
+
+fn show_playback_controls(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut state: ResMut<State<GameState>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let mut playback = match playback {
+        Some(playback) => playback,
+        None => return,
+    };
+    let entries = playback.shown_entries.unwrap_or(0);
+    let entry_count = playback.replay.entries.len();
+    let duration = playback.replay.duration() as f64;
+    egui::Window::new("Replay")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::Vec2::new(0.0, -10.0))
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(entries > 0, egui::Button::new("<"))
+                    .on_hover_text("Previous step")
+                    .clicked()
+                {
+                    step_to(&mut playback, entries - 1);
+                }
+                let play_label = if playback.playing { "Pause" } else { "Play" };
+                if ui.button(play_label).clicked() {
+                    if !playback.playing && playback.position >= duration {
+                        playback.position = 0.0;
+                    }
+                    playback.playing = !playback.playing;
+                }
+                if ui
+                    .add_enabled(entries < entry_count, egui::Button::new(">"))
+                    .on_hover_text("Next step")
+                    .clicked()
+                {
+                    step_to(&mut playback, entries + 1);
+                }
+                ui.add(
+                    egui::Slider::new(&mut playback.speed, MIN_SPEED..=MAX_SPEED)
+                        .logarithmic(true)
+                        .suffix("x"),
+                );
+                if ui.button("Close").clicked() {
+                    if let Err(error) = state.set(GameState::Menu) {
+                        warn!("Failed to close the replay: {:?}", error);
+                    }
+                }
+            });
+            ui.add(
+                egui::Slider::new(&mut playback.position, 0.0..=duration)
+                    .show_value(false)
+                    .text(format!(
+                        "{} / {}",
+                        format_time(playback.position),
+                        format_time(duration)
+                    )),
+            );
+            let last_event = entries
+                .checked_sub(1)
+                .map(|index| describe(&playback.replay, &playback.replay.entries[index].event));
+            ui.label(format!(
+                "Step {} of {}: {}",
+                entries,
+                entry_count,
+                last_event.unwrap_or_else(|| "Start".to_string())
+            ));
+            if let Some(error) = &playback.error {
+                ui.colored_label(egui::Color32::RED, format!("Out of sync. {}", error));
+            }
+        });
+}
+
+fn step_to(playback: &mut ReplayPlayback, entries: usize) {
+    playback.playing = false;
+    playback.position = time_of_entries(&playback.replay, entries);
+    playback.stepped_entries = Some(entries);
+}
+
+fn format_time(milliseconds: f64) -> String {
+    let seconds = (milliseconds / 1000.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn describe(replay: &Replay, event: &ReplayEvent) -> String {
+    match event {
+        ReplayEvent::Action(action) => format!("{:?}", action),
+        ReplayEvent::Synced(game) => match replay.players.get(game.current_player()) {
+            Some(name) => format!("Synced, {} is on turn", name),
+            None => "Synced".to_string(),
+        },
+        ReplayEvent::Joined(name) => format!("{} joined", name),
+        ReplayEvent::Left(name) => format!("{} left", name),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use super::ReplayFileError;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    /// Can be overridden with the environment variable `PIG_HOLE_REPLAY_DIRECTORY`.
+    const DEFAULT_DIRECTORY: &str = "replays";
+    const EXTENSION: &str = "replay";
+
+    fn directory() -> PathBuf {
+        std::env::var("PIG_HOLE_REPLAY_DIRECTORY")
+            .unwrap_or_else(|_| DEFAULT_DIRECTORY.to_string())
+            .into()
+    }
+
+    fn storage_error(error: std::io::Error) -> ReplayFileError {
+        ReplayFileError::Storage(error.to_string())
+    }
+
+    pub fn list() -> Result<Vec<String>, ReplayFileError> {
+        let entries = match fs::read_dir(directory()) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(storage_error(error)),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(storage_error)?.path();
+            if path
+                .extension()
+                .map_or(false, |extension| extension == EXTENSION)
+            {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(names)
+    }
+
+    pub fn read(name: &str) -> Result<Vec<u8>, ReplayFileError> {
+        let path = directory().join(name).with_extension(EXTENSION);
+        fs::read(path).map_err(storage_error)
+    }
+
+    /// Returns the name of the new replay.
+    pub fn write(bytes: &[u8]) -> Result<String, ReplayFileError> {
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let name = format!("match-{}", seconds);
+        fs::create_dir_all(directory()).map_err(storage_error)?;
+        let path = directory().join(&name).with_extension(EXTENSION);
+        fs::write(path, bytes).map_err(storage_error)?;
+        Ok(name)
+    }
+}
+
+/// Browsers have no files and replays are too large for the local storage.
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use super::ReplayFileError;
+
+    fn unsupported() -> ReplayFileError {
+        ReplayFileError::Storage("Replays are not available in the browser".to_string())
+    }
+
+    pub fn list() -> Result<Vec<String>, ReplayFileError> {
+        Ok(Vec::new())
+    }
+
+    pub fn read(_name: &str) -> Result<Vec<u8>, ReplayFileError> {
+        Err(unsupported())
+    }
+
+    pub fn write(_bytes: &[u8]) -> Result<String, ReplayFileError> {
+        Err(unsupported())
+    }
+}
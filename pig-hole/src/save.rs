@@ -0,0 +1,264 @@
+use crate::networking::{
+    run_if_server, AuthoritativeMatch, HostInfo, HostedMatch, NetworkRole, ServerLobby,
+};
+use crate::player_creation::{Seat, Seats};
+use crate::replay::ReplayPlayback;
+use crate::GameState;
+use bevy::prelude::*;
+use rules::UndoPolicy;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+
+/// Increased whenever saves of older versions can no longer be read.
+pub const SAVE_VERSION: u32 = 1;
+
+pub struct SavePlugin;
+
+/// This plugin saves every change of a running match, so it can be continued from the main menu.
+/// Only the machine that runs the rules saves, i.e. hot-seat games and hosts.
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(load_save))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(save_local_game))
+            .add_system(save_hosted_match.with_run_criteria(run_if_server));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub saved_match: SavedMatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavedMatch {
+    /// A hot-seat game, possibly with bots.
+    Local {
+        seats: Vec<Seat>,
+        game: rules::GameState,
+        #[serde(default)]
+        undo_policy: UndoPolicy,
+    },
+    /// A networked game hosted by this machine.
+    Hosted(HostedMatch),
+}
+
+impl SavedMatch {
+    pub fn game(&self) -> &rules::GameState {
+        match self {
+            SavedMatch::Local { game, .. } => game,
+            SavedMatch::Hosted(hosted_match) => hosted_match.game(),
+        }
+    }
+
+    fn seat_count(&self) -> usize {
+        match self {
+            SavedMatch::Local { seats, .. } => seats.len(),
+            SavedMatch::Hosted(hosted_match) => hosted_match.seat_count(),
+        }
+    }
+
+    /// Damaged or edited saves must not make it to the board, where they would crash the game.
+    fn validate(&self) -> Result<(), SaveError> {
+        let game = self.game();
+        if let Err(error) = game.rules().validate() {
+            return Err(SaveError::Format(error.to_string()));
+        }
+        if self.seat_count() != game.player_count() {
+            return Err(SaveError::Format(format!(
+                "{} seats do not fit a game of {} players",
+                self.seat_count(),
+                game.player_count()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Only the version, which is read first so newer fields never break the check.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SaveError {
+    Storage(String),
+    Format(String),
+    UnsupportedVersion(u32),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Storage(error) => write!(f, "Failed to access the saved game: {}", error),
+            SaveError::Format(error) => write!(f, "The saved game is damaged: {}", error),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "The saved game is from version {}, but only version {} is supported",
+                version, SAVE_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+/// The saved match offered in the main menu, read whenever the menu is entered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedGame(pub Option<Result<SaveFile, SaveError>>);
+
+impl SaveFile {
+    pub fn new(saved_match: SavedMatch) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            saved_match,
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        ron::ser::to_string_pretty(self, default())
+            .map_err(|error| SaveError::Format(error.to_string()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, SaveError> {
+        let format_error = |error: ron::Error| SaveError::Format(error.to_string());
+        let header: SaveHeader = ron::from_str(contents).map_err(format_error)?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(header.version));
+        }
+        let save_file: SaveFile = ron::from_str(contents).map_err(format_error)?;
+        save_file.saved_match.validate()?;
+        Ok(save_file)
+    }
+}
+
+fn load_save(mut commands: Commands) {
+    let saved_game = match storage::read() {
+        Ok(Some(contents)) => Some(SaveFile::parse(&contents)),
+        Ok(None) => None,
+        Err(error) => Some(Err(error)),
+    };
+    commands.insert_resource(SavedGame(saved_game));
+}
+
+fn save_local_game(
+    game: Res<rules::GameState>,
+    seats: Res<Seats>,
+    undo_policy: Res<UndoPolicy>,
+    role: Res<NetworkRole>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    // A replay is not a match that can be continued
+    if *role != NetworkRole::Offline || playback.is_some() || !game.is_changed() {
+        return;
+    }
+    store(SavedMatch::Local {
+        seats: seats.0.clone(),
+        game: game.clone(),
+        undo_policy: *undo_policy,
+    });
+}
+
+fn save_hosted_match(
+    host: Option<Res<HostInfo>>,
+    lobby: Option<Res<ServerLobby>>,
+    authoritative_match: Option<Res<AuthoritativeMatch>>,
+) {
+    let (host, lobby, authoritative_match) = match (host, lobby, authoritative_match) {
+        (Some(host), Some(lobby), Some(authoritative_match)) => (host, lobby, authoritative_match),
+        _ => return,
+    };
+    if !authoritative_match.is_changed() {
+        return;
+    }
+    store(SavedMatch::Hosted(HostedMatch::new(
+        host.clone(),
+        &lobby,
+        &authoritative_match,
+    )));
+}
+
+/// Finished matches are deleted, there is nothing left to continue.
+fn store(saved_match: SavedMatch) {
+    let result = match saved_match.game().is_over() {
+        true => storage::delete(),
+        false => SaveFile::new(saved_match)
+            .to_ron()
+            .and_then(|contents| storage::write(&contents)),
+    };
+    if let Err(error) = result {
+        warn!("Failed to save the game: {}", error);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use super::SaveError;
+    use std::io::ErrorKind;
+    use std::{env, fs};
+
+    /// Can be overridden with the environment variable `PIG_HOLE_SAVE_FILE`.
+    const DEFAULT_PATH: &str = "pig-hole.save.ron";
+
+    fn path() -> String {
+        env::var("PIG_HOLE_SAVE_FILE").unwrap_or_else(|_| DEFAULT_PATH.to_string())
+    }
+
+    fn storage_error(error: std::io::Error) -> SaveError {
+        SaveError::Storage(error.to_string())
+    }
+
+    pub fn read() -> Result<Option<String>, SaveError> {
+        match fs::read_to_string(path()) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(storage_error(error)),
+        }
+    }
+
+    pub fn write(contents: &str) -> Result<(), SaveError> {
+        fs::write(path(), contents).map_err(storage_error)
+    }
+
+    pub fn delete() -> Result<(), SaveError> {
+        match fs::remove_file(path()) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(storage_error(error)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Browsers have no files, so the save goes to the local storage of the page.
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use super::SaveError;
+    use web_sys::Storage;
+
+    const KEY: &str = "pig-hole-save";
+
+    fn local_storage() -> Result<Storage, SaveError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| SaveError::Storage("local storage is not available".to_string()))
+    }
+
+    fn storage_error(error: wasm_bindgen::JsValue) -> SaveError {
+        SaveError::Storage(format!("{:?}", error))
+    }
+
+    pub fn read() -> Result<Option<String>, SaveError> {
+        local_storage()?.get_item(KEY).map_err(storage_error)
+    }
+
+    pub fn write(contents: &str) -> Result<(), SaveError> {
+        local_storage()?
+            .set_item(KEY, contents)
+            .map_err(storage_error)
+    }
+
+    pub fn delete() -> Result<(), SaveError> {
+        local_storage()?.remove_item(KEY).map_err(storage_error)
+    }
+}
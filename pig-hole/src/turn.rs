@@ -36,21 +36,14 @@ impl Turn {
             current_player_index: 0,
         }
     }
-    pub fn get_min_actions(&self) -> Option<usize> {
-        match self.number {
-            0 => panic!("Turn 0 is invalid"),
-            n if n <= 2 => Some(n),
-            _ => None,
-        }
-    }
 
-    pub fn start_next_players_turn(&mut self) -> Entity {
-        self.current_player_index += 1;
-        if self.current_player_index >= self.player_order.len() {
-            self.current_player_index = 0;
-            self.number += 1;
-        }
-        self.get_current_player()
+    /// Follows the turn of the given game.
+    /// Returns whether a new turn has started.
+    pub fn sync_with(&mut self, game: &rules::GameState) -> bool {
+        let previous = (self.number, self.current_player_index);
+        self.number = game.turn_number();
+        self.current_player_index = game.current_player();
+        previous != (self.number, self.current_player_index)
     }
 
     pub fn get_current_player(&self) -> Entity {
@@ -60,6 +53,10 @@ impl Turn {
     pub fn get_turn_number(&self) -> usize {
         self.number
     }
+
+    pub fn get_player_order(&self) -> &[Entity] {
+        &self.player_order
+    }
 }
 
 impl Display for Turn {
@@ -76,12 +73,12 @@ impl Display for Turn {
 
 fn set_current_player(
     mut commands: Commands,
-    mut turn: ResMut<Turn>,
+    turn: Res<Turn>,
     player_query: Query<Entity, With<Player>>,
     mut turn_change_event_reader: EventReader<TurnChangeEvent>,
 ) {
     for _event in turn_change_event_reader.iter() {
-        let current_player = turn.start_next_players_turn();
+        let current_player = turn.get_current_player();
         for entity in player_query.iter() {
             let mut player = commands.entity(entity);
             if entity == current_player {
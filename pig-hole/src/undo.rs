@@ -0,0 +1,123 @@
+use crate::handover::Handover;
+use crate::networking::NetworkRole;
+use crate::player::LocalPlayer;
+use crate::turn::IsOnTurn;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use rules::{History, ReplayEvent, UndoPolicy};
+
+pub struct UndoPlugin;
+
+/// This plugin lets players take back their moves with `Ctrl+Z` and make them again with `Ctrl+Y`.
+/// Only offline games can be undone, since in networked games the host decides for everyone.
+impl Plugin for UndoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UndoPolicy>()
+            .init_resource::<History>()
+            .add_event::<UndoRequest>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_history))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(read_shortcuts)
+                    .with_system(show_undo_controls)
+                    .with_system(handle_undo_requests),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum UndoRequest {
+    Undo,
+    Redo,
+}
+
+fn reset_history(mut history: ResMut<History>, policy: Res<UndoPolicy>) {
+    *history = History::new(*policy);
+}
+
+/// Moves may only be taken back by a human on this machine while it is their turn.
+fn can_take_back(
+    role: &NetworkRole,
+    handover: &Handover,
+    local_player_query: &Query<(), (With<IsOnTurn>, With<LocalPlayer>)>,
+) -> bool {
+    *role == NetworkRole::Offline && !handover.is_pending() && !local_player_query.is_empty()
+}
+
+fn read_shortcuts(keyboard_input: Res<Input<KeyCode>>, mut requests: EventWriter<UndoRequest>) {
+    let control = keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if !control {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Z) {
+        requests.send(UndoRequest::Undo);
+    } else if keyboard_input.just_pressed(KeyCode::Y) {
+        requests.send(UndoRequest::Redo);
+    }
+}
+
+fn show_undo_controls(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut requests: EventWriter<UndoRequest>,
+    history: Res<History>,
+    role: Res<NetworkRole>,
+    handover: Res<Handover>,
+    local_player_query: Query<(), (With<IsOnTurn>, With<LocalPlayer>)>,
+) {
+    if history.policy() == UndoPolicy::Off || !can_take_back(&role, &handover, &local_player_query)
+    {
+        return;
+    }
+    egui::Window::new("Undo")
+        .title_bar(false)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_TOP, egui::Vec2::new(-10.0, 10.0))
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(history.can_undo(), egui::Button::new("Undo"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    requests.send(UndoRequest::Undo);
+                }
+                if ui
+                    .add_enabled(history.can_redo(), egui::Button::new("Redo"))
+                    .on_hover_text("Ctrl+Y")
+                    .clicked()
+                {
+                    requests.send(UndoRequest::Redo);
+                }
+            });
+        });
+}
+
+fn handle_undo_requests(
+    mut requests: EventReader<UndoRequest>,
+    mut history: ResMut<History>,
+    mut game: ResMut<rules::GameState>,
+    mut replay_events: EventWriter<ReplayEvent>,
+    role: Res<NetworkRole>,
+    handover: Res<Handover>,
+    local_player_query: Query<(), (With<IsOnTurn>, With<LocalPlayer>)>,
+) {
+    for request in requests.iter() {
+        if !can_take_back(&role, &handover, &local_player_query) {
+            continue;
+        }
+        match request {
+            UndoRequest::Undo => {
+                if let Some(action) = history.undo(&mut game) {
+                    info!("Took back {:?}", action);
+                    replay_events.send(ReplayEvent::Synced(game.clone()));
+                }
+            }
+            UndoRequest::Redo => {
+                if let Some((action, _)) = history.redo(&mut game) {
+                    replay_events.send(ReplayEvent::Action(action));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Numbers that help with the only real decision of the game: roll again or end the turn.
+
+use crate::bot::{self, Difficulty};
+use crate::game::GameState;
+use rand::Rng;
+
+/// Probability that the next roll lands on a full group, which has to be collected.
+pub fn collection_probability(game: &GameState) -> f64 {
+    game.rules()
+        .roll_probabilities()
+        .into_iter()
+        .filter(|&(roll, _)| game.board().is_group_full(roll))
+        .map(|(_, probability)| probability)
+        .sum()
+}
+
+/// How many pigs a player is expected to gain by rolling once more.
+/// Negative values mean that rolling is expected to get rid of pigs.
+pub fn expected_pig_delta(game: &GameState) -> f64 {
+    let board = game.board();
+    game.rules()
+        .roll_probabilities()
+        .into_iter()
+        .map(|(roll, probability)| match board.is_group_full(roll) {
+            true => probability * f64::from(board.occupied_count(roll)),
+            false => -probability,
+        })
+        .sum()
+}
+
+/// Estimates the chance of every player to win by playing `samples` games to the end,
+/// with every player following the given bot strategy.
+pub fn win_probabilities(
+    game: &GameState,
+    difficulty: Difficulty,
+    samples: usize,
+    rng: &mut impl Rng,
+) -> Vec<f64> {
+    let mut wins = vec![0; game.player_count()];
+    for _ in 0..samples {
+        if let Some(winner) = play_out(game.clone(), difficulty, rng) {
+            wins[winner] += 1;
+        }
+    }
+    wins.into_iter()
+        .map(|count| count as f64 / samples.max(1) as f64)
+        .collect()
+}
+
+/// Plays the game to the end and returns the winner, unless it takes more than `bot::MAX_ACTIONS`.
+fn play_out(mut game: GameState, difficulty: Difficulty, rng: &mut impl Rng) -> Option<usize> {
+    for _ in 0..bot::MAX_ACTIONS {
+        let action = match bot::next_action(&game, difficulty, rng) {
+            Some(action) => action,
+            None => break,
+        };
+        game.apply(action).ok()?;
+    }
+    game.winner()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::{play_roll, STARTING_PIGS};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn rolling_on_an_empty_board_always_places_a_pig() {
+        let game = GameState::new(2);
+        assert_eq!(collection_probability(&game), 0.0);
+        assert!((expected_pig_delta(&game) + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn full_groups_make_rolling_riskier() {
+        let mut game = GameState::new(1);
+        play_roll(&mut game, 1, (1, 1));
+        play_roll(&mut game, 2, (2, 1));
+        play_roll(&mut game, 2, (2, 2));
+        assert!((collection_probability(&game) - 2.0 / 6.0).abs() < 1e-9);
+        assert!((expected_pig_delta(&game) - (3.0 - 4.0) / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn win_probabilities_add_up_to_one() {
+        let game = GameState::new(3);
+        let mut rng = StdRng::seed_from_u64(3);
+        let probabilities = win_probabilities(&game, Difficulty::Expectation, 50, &mut rng);
+        assert_eq!(probabilities.len(), 3);
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn finished_games_are_certain() {
+        let mut game = GameState::new(1);
+        for _ in 0..STARTING_PIGS {
+            play_roll(&mut game, 6, (6, 1));
+        }
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            win_probabilities(&game, Difficulty::Random, 10, &mut rng),
+            vec![1.0]
+        );
+    }
+}
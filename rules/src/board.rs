@@ -0,0 +1,202 @@
+use crate::rule_set::RuleSet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// The group whose only trough is the pig hole on the classic board.
+/// Pigs placed in it are gone for good.
+pub const PIG_HOLE: u8 = 6;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Trough {
+    pub group: u8,
+    pub index: u8,
+}
+
+impl From<(u8, u8)> for Trough {
+    fn from((group, index): (u8, u8)) -> Self {
+        Self { group, index }
+    }
+}
+
+impl Display for Trough {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.group, self.index)
+    }
+}
+
+/// Occupancy of every trough on the board.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Board {
+    troughs: BTreeMap<Trough, bool>,
+    /// The group that swallows pigs, if the rules have one.
+    pig_hole: Option<u8>,
+}
+
+impl Default for Board {
+    /// The classic board: group `n` has `n` troughs for 1 to 5, and group 6 is the pig hole.
+    fn default() -> Self {
+        Self::new(&RuleSet::default())
+    }
+}
+
+impl Board {
+    /// Group `n` has `n` troughs for every roll but the highest, whose group is a single trough.
+    pub fn new(rules: &RuleSet) -> Self {
+        let highest_roll = rules.highest_roll();
+        let troughs = (rules.lowest_roll()..highest_roll)
+            .flat_map(|group| (1..=group).map(move |index| Trough { group, index }))
+            .chain(std::iter::once(Trough {
+                group: highest_roll,
+                index: 1,
+            }))
+            .map(|trough| (trough, false))
+            .collect();
+        Self {
+            troughs,
+            pig_hole: rules.bottomless_pig_hole.then_some(highest_roll),
+        }
+    }
+
+    /// Every group on the board, in ascending order.
+    pub fn groups(&self) -> impl Iterator<Item = u8> + '_ {
+        let mut previous = None;
+        self.troughs().filter_map(move |trough| {
+            let is_new = previous != Some(trough.group);
+            previous = Some(trough.group);
+            is_new.then_some(trough.group)
+        })
+    }
+
+    pub fn troughs(&self) -> impl Iterator<Item = Trough> + '_ {
+        self.troughs.keys().copied()
+    }
+
+    pub fn troughs_in_group(&self, group: u8) -> impl Iterator<Item = Trough> + '_ {
+        self.troughs().filter(move |trough| trough.group == group)
+    }
+
+    pub fn contains(&self, trough: Trough) -> bool {
+        self.troughs.contains_key(&trough)
+    }
+
+    pub fn has_group(&self, group: u8) -> bool {
+        self.troughs_in_group(group).next().is_some()
+    }
+
+    pub fn is_occupied(&self, trough: Trough) -> bool {
+        self.troughs.get(&trough).copied().unwrap_or_default()
+    }
+
+    pub fn is_pig_hole(&self, group: u8) -> bool {
+        self.pig_hole == Some(group)
+    }
+
+    /// The pig hole is bottomless, so it is never full.
+    pub fn is_group_full(&self, group: u8) -> bool {
+        !self.is_pig_hole(group)
+            && self
+                .troughs_in_group(group)
+                .all(|trough| self.is_occupied(trough))
+    }
+
+    pub fn occupied_count(&self, group: u8) -> u32 {
+        self.troughs_in_group(group)
+            .filter(|&trough| self.is_occupied(trough))
+            .count() as u32
+    }
+
+    pub(crate) fn occupy(&mut self, trough: Trough) {
+        if let Some(occupied) = self.troughs.get_mut(&trough) {
+            *occupied = true;
+        }
+    }
+
+    /// Empties every trough of the group and returns how many pigs were in it.
+    pub(crate) fn clear_group(&mut self, group: u8) -> u32 {
+        let mut pigs = 0;
+        for (_, occupied) in self
+            .troughs
+            .iter_mut()
+            .filter(|(trough, _)| trough.group == group)
+        {
+            if *occupied {
+                pigs += 1;
+            }
+            *occupied = false;
+        }
+        pigs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_board_has_classic_layout() {
+        let board = Board::default();
+        for group in 1..PIG_HOLE {
+            assert_eq!(board.troughs_in_group(group).count(), group as usize);
+        }
+        assert_eq!(board.troughs_in_group(PIG_HOLE).count(), 1);
+        assert!(!board.has_group(0));
+        assert!(!board.has_group(7));
+    }
+
+    #[test]
+    fn two_dice_start_at_group_two() {
+        let rules = RuleSet {
+            dice: 2,
+            ..RuleSet::default()
+        };
+        let board = Board::new(&rules);
+        assert_eq!(
+            board.groups().collect::<Vec<_>>(),
+            (2..=12).collect::<Vec<_>>()
+        );
+        assert_eq!(board.troughs_in_group(11).count(), 11);
+        assert_eq!(board.troughs_in_group(12).count(), 1);
+        assert!(board.is_pig_hole(12));
+        assert!(!board.is_pig_hole(PIG_HOLE));
+    }
+
+    #[test]
+    fn group_is_full_when_every_trough_is_occupied() {
+        let mut board = Board::default();
+        board.occupy((2, 1).into());
+        assert!(!board.is_group_full(2));
+        board.occupy((2, 2).into());
+        assert!(board.is_group_full(2));
+    }
+
+    #[test]
+    fn pig_hole_is_never_full() {
+        let mut board = Board::default();
+        board.occupy((PIG_HOLE, 1).into());
+        assert!(!board.is_group_full(PIG_HOLE));
+    }
+
+    #[test]
+    fn pig_hole_can_be_filled_if_it_has_a_bottom() {
+        let rules = RuleSet {
+            bottomless_pig_hole: false,
+            ..RuleSet::default()
+        };
+        let mut board = Board::new(&rules);
+        board.occupy((PIG_HOLE, 1).into());
+        assert!(!board.is_pig_hole(PIG_HOLE));
+        assert!(board.is_group_full(PIG_HOLE));
+    }
+
+    #[test]
+    fn clearing_a_group_returns_its_pigs() {
+        let mut board = Board::default();
+        board.occupy((3, 1).into());
+        board.occupy((3, 3).into());
+        board.occupy((4, 1).into());
+        assert_eq!(board.clear_group(3), 2);
+        assert_eq!(board.occupied_count(3), 0);
+        assert_eq!(board.occupied_count(4), 1);
+    }
+}
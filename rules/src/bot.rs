@@ -0,0 +1,193 @@
+use crate::analysis;
+use crate::board::Trough;
+use crate::game::{Action, GameState, Phase};
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Games between bots that are still running after this many actions are given up on.
+pub const MAX_ACTIONS: usize = 100_000;
+
+/// How well a computer opponent plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Picks any allowed move at random.
+    Random,
+    /// Stops rolling as soon as a roll could cost pigs.
+    Cautious,
+    /// Keeps rolling while another roll is expected to get rid of pigs.
+    Expectation,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [
+        Difficulty::Random,
+        Difficulty::Cautious,
+        Difficulty::Expectation,
+    ];
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Random => write!(f, "Easy"),
+            Difficulty::Cautious => write!(f, "Medium"),
+            Difficulty::Expectation => write!(f, "Hard"),
+        }
+    }
+}
+
+/// What a bot wants to do next. Rolls are left to whoever runs the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Decision {
+    Roll,
+    Place(Trough),
+    Collect(u8),
+    EndTurn,
+}
+
+/// The next move of the current player, or `None` if the game is over.
+pub fn decide(game: &GameState, difficulty: Difficulty, rng: &mut impl Rng) -> Option<Decision> {
+    match game.phase() {
+        Phase::Finished => None,
+        Phase::CollectingGroup(group) => Some(Decision::Collect(group)),
+        Phase::PlacingInGroup(group) => {
+            let mut free_troughs = game
+                .board()
+                .troughs_in_group(group)
+                .filter(|&trough| !game.board().is_occupied(trough));
+            let trough = match difficulty {
+                Difficulty::Random => free_troughs.choose(rng),
+                _ => free_troughs.next(),
+            };
+            trough.map(Decision::Place)
+        }
+        Phase::Rolling if !game.can_end_turn() => Some(Decision::Roll),
+        Phase::Rolling => {
+            let keep_rolling = match difficulty {
+                Difficulty::Random => rng.gen_bool(0.5),
+                Difficulty::Cautious => analysis::collection_probability(game) == 0.0,
+                Difficulty::Expectation => expected_pig_change(game) < 0.0,
+            };
+            Some(if keep_rolling {
+                Decision::Roll
+            } else {
+                Decision::EndTurn
+            })
+        }
+    }
+}
+
+/// The next action of the current player, with dice rolls made by `rng`.
+pub fn next_action(game: &GameState, difficulty: Difficulty, rng: &mut impl Rng) -> Option<Action> {
+    let action = match decide(game, difficulty, rng)? {
+        Decision::Roll => Action::Roll(game.rules().roll(rng)),
+        Decision::Place(trough) => Action::Place(trough),
+        Decision::Collect(group) => Action::Collect(group),
+        Decision::EndTurn => Action::EndTurn,
+    };
+    Some(action)
+}
+
+/// Like [`analysis::expected_pig_delta`], but winning with the roll
+/// counts as getting rid of all pigs at once.
+pub fn expected_pig_change(game: &GameState) -> f64 {
+    let delta = analysis::expected_pig_delta(game);
+    match game.pig_count(game.current_player()) {
+        1 => {
+            let placing = 1.0 - analysis::collection_probability(game);
+            delta - placing * f64::from(game.rules().starting_pigs - 1)
+        }
+        _ => delta,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::play_roll;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn bots_roll_while_rolls_are_mandatory() {
+        let game = GameState::new(2);
+        let mut rng = StdRng::seed_from_u64(0);
+        for difficulty in Difficulty::ALL {
+            assert_eq!(decide(&game, difficulty, &mut rng), Some(Decision::Roll));
+        }
+    }
+
+    #[test]
+    fn bots_place_in_a_free_trough_of_the_rolled_group() {
+        let mut game = GameState::new(2);
+        game.apply(Action::Roll(2)).unwrap();
+        game.apply(Action::Place((2, 1).into())).unwrap();
+        game.apply(Action::Roll(2)).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for difficulty in Difficulty::ALL {
+            assert_eq!(
+                decide(&game, difficulty, &mut rng),
+                Some(Decision::Place((2, 2).into()))
+            );
+        }
+    }
+
+    #[test]
+    fn bots_collect_full_groups() {
+        let mut game = GameState::new(2);
+        game.apply(Action::Roll(1)).unwrap();
+        game.apply(Action::Place((1, 1).into())).unwrap();
+        game.apply(Action::Roll(1)).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            decide(&game, Difficulty::Cautious, &mut rng),
+            Some(Decision::Collect(1))
+        );
+    }
+
+    #[test]
+    fn cautious_bots_stop_once_a_group_is_full() {
+        let mut game = play_until_free_turn();
+        let mut rng = StdRng::seed_from_u64(0);
+        play_roll(&mut game, 1, (1, 1));
+        assert_eq!(
+            decide(&game, Difficulty::Cautious, &mut rng),
+            Some(Decision::EndTurn)
+        );
+    }
+
+    #[test]
+    fn expectation_bots_keep_rolling_on_an_empty_board() {
+        let mut game = play_until_free_turn();
+        let mut rng = StdRng::seed_from_u64(0);
+        play_roll(&mut game, 6, (6, 1));
+        assert!(expected_pig_change(&game) < 0.0);
+        assert_eq!(
+            decide(&game, Difficulty::Expectation, &mut rng),
+            Some(Decision::Roll)
+        );
+    }
+
+    #[test]
+    fn bots_play_a_whole_game() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for difficulty in Difficulty::ALL {
+            let mut game = GameState::new(3);
+            while let Some(action) = next_action(&game, difficulty, &mut rng) {
+                game.apply(action).unwrap();
+            }
+            assert!(game.winner().is_some());
+        }
+    }
+
+    /// Plays the mandatory turns of a single player game using only the pig hole.
+    fn play_until_free_turn() -> GameState {
+        let mut game = GameState::new(1);
+        while game.mandatory_rolls().is_some() {
+            play_roll(&mut game, 6, (6, 1));
+        }
+        game
+    }
+}
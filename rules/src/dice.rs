@@ -0,0 +1,228 @@
+use crate::game::DICE_FACES;
+use crate::rule_set::RuleSet;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fmt::Display;
+
+pub type Seed = [u8; 32];
+pub type Commitment = [u8; 32];
+
+/// Dice whose rolls are all derived from a secret seed.
+///
+/// Publishing the [`Commitment`] when a match starts and revealing the seed when it ends
+/// lets every player check with [`verify`] that no roll was picked by hand.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FairDice {
+    seed: Seed,
+    rolls: u64,
+    dice: u8,
+    faces: u8,
+}
+
+impl FairDice {
+    /// A single die with the classic six faces.
+    pub fn new(seed: Seed) -> Self {
+        Self {
+            seed,
+            rolls: 0,
+            dice: 1,
+            faces: DICE_FACES,
+        }
+    }
+
+    pub fn for_rules(seed: Seed, rules: &RuleSet) -> Self {
+        Self {
+            dice: rules.dice,
+            faces: rules.faces,
+            ..Self::new(seed)
+        }
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        commit(&self.seed)
+    }
+
+    /// Only reveal this once the match is over.
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    /// The result of the next roll, without using it up.
+    pub fn peek(&self) -> u8 {
+        nth_roll(&self.seed, self.rolls, self.dice, self.faces)
+    }
+
+    pub fn roll(&mut self) -> u8 {
+        let roll = self.peek();
+        self.rolls += 1;
+        roll
+    }
+}
+
+pub fn commit(seed: &Seed) -> Commitment {
+    Sha256::digest(seed).into()
+}
+
+/// The roll with the given index for a seed, which is the sum of all dice.
+/// Uses rejection sampling on the hash bytes so that every face is equally likely.
+pub fn nth_roll(seed: &Seed, n: u64, dice: u8, faces: u8) -> u8 {
+    let limit = u8::MAX - (u8::MAX % faces);
+    (0u32..)
+        .flat_map(|attempt| {
+            Sha256::new()
+                .chain_update(seed)
+                .chain_update(n.to_le_bytes())
+                .chain_update(attempt.to_le_bytes())
+                .finalize()
+        })
+        .filter(|&byte| byte < limit)
+        .take(usize::from(dice))
+        .map(|byte| byte % faces + 1)
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FairnessError {
+    /// The revealed seed is not the one that was committed to.
+    WrongSeed,
+    /// The roll with the given index does not follow from the seed.
+    ManipulatedRoll {
+        index: usize,
+        expected: u8,
+        actual: u8,
+    },
+}
+
+impl Display for FairnessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FairnessError::WrongSeed => write!(f, "The revealed seed does not match"),
+            FairnessError::ManipulatedRoll {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Roll {} should have been a {}, but was a {}",
+                index + 1,
+                expected,
+                actual
+            ),
+        }
+    }
+}
+
+impl Error for FairnessError {}
+
+/// Checks that the revealed seed matches the commitment and produced exactly the given rolls.
+pub fn verify(
+    commitment: &Commitment,
+    seed: &Seed,
+    rolls: &[u8],
+    rules: &RuleSet,
+) -> Result<(), FairnessError> {
+    if commit(seed) != *commitment {
+        return Err(FairnessError::WrongSeed);
+    }
+    for (index, &actual) in rolls.iter().enumerate() {
+        let expected = nth_roll(seed, index as u64, rules.dice, rules.faces);
+        if expected != actual {
+            return Err(FairnessError::ManipulatedRoll {
+                index,
+                expected,
+                actual,
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: Seed = [7; 32];
+
+    #[test]
+    fn rolls_are_deterministic() {
+        let mut first = FairDice::new(SEED);
+        let mut second = FairDice::new(SEED);
+        for _ in 0..100 {
+            assert_eq!(first.roll(), second.roll());
+        }
+    }
+
+    #[test]
+    fn peeking_does_not_use_up_the_roll() {
+        let mut dice = FairDice::new(SEED);
+        let peeked = dice.peek();
+        assert_eq!(dice.peek(), peeked);
+        assert_eq!(dice.roll(), peeked);
+    }
+
+    #[test]
+    fn every_face_is_rolled() {
+        let mut dice = FairDice::new(SEED);
+        let mut counts = [0; DICE_FACES as usize];
+        for _ in 0..6000 {
+            let roll = dice.roll();
+            assert!((1..=DICE_FACES).contains(&roll));
+            counts[roll as usize - 1] += 1;
+        }
+        assert!(counts.iter().all(|&count| count > 800), "{:?}", counts);
+    }
+
+    #[test]
+    fn several_dice_are_summed_up() {
+        let rules = RuleSet {
+            dice: 3,
+            faces: 4,
+            ..RuleSet::default()
+        };
+        let mut dice = FairDice::for_rules(SEED, &rules);
+        let commitment = dice.commitment();
+        let rolls: Vec<_> = (0..200).map(|_| dice.roll()).collect();
+        assert!(rolls.iter().all(|roll| (3..=12).contains(roll)));
+        assert!(rolls.contains(&3) && rolls.contains(&12));
+        assert_eq!(verify(&commitment, &SEED, &rolls, &rules), Ok(()));
+    }
+
+    #[test]
+    fn honest_rolls_are_verified() {
+        let mut dice = FairDice::new(SEED);
+        let commitment = dice.commitment();
+        let rolls: Vec<_> = (0..50).map(|_| dice.roll()).collect();
+        assert_eq!(
+            verify(&commitment, &dice.seed(), &rolls, &RuleSet::default()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn manipulated_rolls_are_detected() {
+        let mut dice = FairDice::new(SEED);
+        let commitment = dice.commitment();
+        let mut rolls: Vec<_> = (0..10).map(|_| dice.roll()).collect();
+        rolls[4] = rolls[4] % DICE_FACES + 1;
+        assert!(matches!(
+            verify(&commitment, &dice.seed(), &rolls, &RuleSet::default()),
+            Err(FairnessError::ManipulatedRoll { index: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn swapped_seeds_are_detected() {
+        let commitment = FairDice::new(SEED).commitment();
+        let other_seed = [8; 32];
+        let rolls = [nth_roll(&other_seed, 0, 1, DICE_FACES)];
+        assert_eq!(
+            verify(&commitment, &other_seed, &rolls, &RuleSet::default()),
+            Err(FairnessError::WrongSeed)
+        );
+    }
+}
@@ -0,0 +1,504 @@
+use crate::board::{Board, Trough};
+use crate::rule_set::RuleSet;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+
+/// Pigs of every player in the classic rules.
+pub const STARTING_PIGS: u32 = 20;
+/// Faces of the die in the classic rules.
+pub const DICE_FACES: u8 = 6;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Roll the dice. The result is decided by the caller, which keeps the rules deterministic.
+    Roll(u8),
+    Place(Trough),
+    Collect(u8),
+    EndTurn,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Phase {
+    Rolling,
+    PlacingInGroup(u8),
+    CollectingGroup(u8),
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Event {
+    Rolled { player: usize, roll: u8 },
+    Placed { player: usize, trough: Trough },
+    PlacedInPigHole { player: usize, trough: Trough },
+    Collected { player: usize, group: u8, pigs: u32 },
+    TurnEnded { player: usize, next_player: usize },
+    Won { player: usize },
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum RuleError {
+    GameOver,
+    InvalidRoll(u8),
+    UnexpectedAction { action: Action, phase: Phase },
+    WrongGroup { expected: u8, actual: u8 },
+    UnknownTrough(Trough),
+    TroughOccupied(Trough),
+    RollsRemaining(usize),
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::GameOver => write!(f, "The game is already over"),
+            RuleError::InvalidRoll(roll) => write!(f, "{} is not a valid roll", roll),
+            RuleError::UnexpectedAction { action, phase } => {
+                write!(f, "{:?} is not allowed while {:?}", action, phase)
+            }
+            RuleError::WrongGroup { expected, actual } => {
+                write!(f, "Expected group {}, got group {}", expected, actual)
+            }
+            RuleError::UnknownTrough(trough) => write!(f, "There is no trough {}", trough),
+            RuleError::TroughOccupied(trough) => write!(f, "Trough {} is already occupied", trough),
+            RuleError::RollsRemaining(rolls) => {
+                write!(f, "{} more roll(s) needed before ending the turn", rolls)
+            }
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+/// Everything needed to continue a match of Pig Hole.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct GameState {
+    rules: RuleSet,
+    board: Board,
+    pig_counts: Vec<u32>,
+    turn_number: usize,
+    current_player: usize,
+    action_count: usize,
+    phase: Phase,
+}
+
+impl GameState {
+    /// A game with the classic rules.
+    pub fn new(player_count: usize) -> Self {
+        Self::with_rules(player_count, RuleSet::default())
+    }
+
+    pub fn with_rules(player_count: usize, rules: RuleSet) -> Self {
+        if player_count == 0 {
+            panic!("A game needs at least one player");
+        }
+        if let Err(error) = rules.validate() {
+            panic!("Invalid rules: {}", error);
+        }
+        Self {
+            board: rules.board(),
+            pig_counts: vec![rules.starting_pigs; player_count],
+            rules,
+            turn_number: 1,
+            current_player: 0,
+            action_count: 0,
+            phase: Phase::Rolling,
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.pig_counts.len()
+    }
+
+    pub fn pig_count(&self, player: usize) -> u32 {
+        self.pig_counts[player]
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.current_player
+    }
+
+    pub fn turn_number(&self) -> usize {
+        self.turn_number
+    }
+
+    /// Pigs placed by the current player during this turn.
+    pub fn action_count(&self) -> usize {
+        self.action_count
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// The exact number of rolls required this turn, or `None` if the player may roll freely.
+    pub fn mandatory_rolls(&self) -> Option<usize> {
+        self.rules.opening_rolls.get(self.turn_number - 1).copied()
+    }
+
+    /// The most rolls the current player may make this turn, if limited at all.
+    pub fn roll_limit(&self) -> Option<usize> {
+        self.mandatory_rolls().or(self.rules.max_rolls_per_turn)
+    }
+
+    /// How many more rolls the current player has to make before the turn may end.
+    pub fn rolls_remaining(&self) -> usize {
+        match self.mandatory_rolls() {
+            Some(min) => min.saturating_sub(self.action_count),
+            None if self.action_count == 0 => 1,
+            None => 0,
+        }
+    }
+
+    pub fn can_roll(&self) -> bool {
+        self.phase == Phase::Rolling
+    }
+
+    pub fn can_end_turn(&self) -> bool {
+        self.phase == Phase::Rolling && self.rolls_remaining() == 0
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        match self.phase {
+            Phase::Finished => self.pig_counts.iter().position(|&count| count == 0),
+            _ => None,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.phase == Phase::Finished
+    }
+
+    /// Performs an action for the current player.
+    /// On error, the state is left untouched.
+    pub fn apply(&mut self, action: Action) -> Result<Vec<Event>, RuleError> {
+        let player = self.current_player;
+        match (action, self.phase) {
+            (_, Phase::Finished) => Err(RuleError::GameOver),
+            (Action::Roll(roll), Phase::Rolling) => {
+                if !self.board.has_group(roll) {
+                    return Err(RuleError::InvalidRoll(roll));
+                }
+                self.phase = if self.board.is_group_full(roll) {
+                    Phase::CollectingGroup(roll)
+                } else {
+                    Phase::PlacingInGroup(roll)
+                };
+                Ok(vec![Event::Rolled { player, roll }])
+            }
+            (Action::Place(trough), Phase::PlacingInGroup(group)) => {
+                if trough.group != group {
+                    return Err(RuleError::WrongGroup {
+                        expected: group,
+                        actual: trough.group,
+                    });
+                }
+                if !self.board.contains(trough) {
+                    return Err(RuleError::UnknownTrough(trough));
+                }
+                if self.board.is_occupied(trough) {
+                    return Err(RuleError::TroughOccupied(trough));
+                }
+                Ok(self.place(trough))
+            }
+            (Action::Collect(actual), Phase::CollectingGroup(group)) => {
+                if actual != group {
+                    return Err(RuleError::WrongGroup {
+                        expected: group,
+                        actual,
+                    });
+                }
+                let pigs = self.board.clear_group(group);
+                self.pig_counts[player] += pigs;
+                let mut events = vec![Event::Collected {
+                    player,
+                    group,
+                    pigs,
+                }];
+                events.push(self.end_turn());
+                Ok(events)
+            }
+            (Action::EndTurn, Phase::Rolling) => match self.rolls_remaining() {
+                0 => Ok(vec![self.end_turn()]),
+                rolls => Err(RuleError::RollsRemaining(rolls)),
+            },
+            (action, phase) => Err(RuleError::UnexpectedAction { action, phase }),
+        }
+    }
+
+    fn place(&mut self, trough: Trough) -> Vec<Event> {
+        let player = self.current_player;
+        let mut events = Vec::new();
+        if self.board.is_pig_hole(trough.group) {
+            events.push(Event::PlacedInPigHole { player, trough });
+        } else {
+            self.board.occupy(trough);
+            events.push(Event::Placed { player, trough });
+        }
+        self.pig_counts[player] = self.pig_counts[player].saturating_sub(1);
+        self.action_count += 1;
+
+        if self.pig_counts[player] == 0 {
+            self.phase = Phase::Finished;
+            events.push(Event::Won { player });
+        } else if matches!(self.roll_limit(), Some(limit) if self.action_count >= limit) {
+            events.push(self.end_turn());
+        } else {
+            self.phase = Phase::Rolling;
+        }
+        events
+    }
+
+    fn end_turn(&mut self) -> Event {
+        let player = self.current_player;
+        self.action_count = 0;
+        self.current_player += 1;
+        if self.current_player >= self.player_count() {
+            self.current_player = 0;
+            self.turn_number += 1;
+        }
+        self.phase = Phase::Rolling;
+        Event::TurnEnded {
+            player,
+            next_player: self.current_player,
+        }
+    }
+}
+
+/// Rolls and places the pig in the given trough, to set up the board in tests.
+#[cfg(test)]
+pub(crate) fn play_roll(game: &mut GameState, roll: u8, trough: (u8, u8)) {
+    game.apply(Action::Roll(roll)).unwrap();
+    game.apply(Action::Place(trough.into())).unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::PIG_HOLE;
+
+    #[test]
+    fn first_turn_ends_after_one_placement() {
+        let mut game = GameState::new(2);
+        game.apply(Action::Roll(3)).unwrap();
+        let events = game.apply(Action::Place((3, 2).into())).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::Placed {
+                    player: 0,
+                    trough: (3, 2).into()
+                },
+                Event::TurnEnded {
+                    player: 0,
+                    next_player: 1
+                }
+            ]
+        );
+        assert_eq!(game.current_player(), 1);
+        assert_eq!(game.pig_count(0), STARTING_PIGS - 1);
+    }
+
+    #[test]
+    fn second_turn_requires_two_rolls() {
+        let mut game = GameState::new(1);
+        play_roll(&mut game, 5, (5, 1));
+        assert_eq!(game.turn_number(), 2);
+
+        play_roll(&mut game, 5, (5, 2));
+        assert_eq!(game.turn_number(), 2);
+        assert_eq!(
+            game.apply(Action::EndTurn),
+            Err(RuleError::RollsRemaining(1))
+        );
+
+        play_roll(&mut game, 5, (5, 3));
+        assert_eq!(game.turn_number(), 3);
+    }
+
+    #[test]
+    fn free_turns_need_at_least_one_roll_and_are_ended_by_the_player() {
+        let mut game = play_mandatory_turns();
+        assert!(!game.can_end_turn());
+        assert_eq!(
+            game.apply(Action::EndTurn),
+            Err(RuleError::RollsRemaining(1))
+        );
+
+        play_roll(&mut game, 4, (4, 1));
+        play_roll(&mut game, 4, (4, 2));
+        assert_eq!(game.current_player(), 0);
+        assert!(game.can_end_turn());
+
+        let events = game.apply(Action::EndTurn).unwrap();
+        assert_eq!(
+            events,
+            vec![Event::TurnEnded {
+                player: 0,
+                next_player: 0
+            }]
+        );
+        assert_eq!(game.turn_number(), 4);
+    }
+
+    #[test]
+    fn full_group_must_be_collected() {
+        let mut game = GameState::new(2);
+        play_roll(&mut game, 1, (1, 1));
+
+        game.apply(Action::Roll(1)).unwrap();
+        assert_eq!(game.phase(), Phase::CollectingGroup(1));
+        assert!(matches!(
+            game.apply(Action::Place((1, 1).into())),
+            Err(RuleError::UnexpectedAction { .. })
+        ));
+        assert_eq!(
+            game.apply(Action::Collect(2)),
+            Err(RuleError::WrongGroup {
+                expected: 1,
+                actual: 2
+            })
+        );
+
+        let events = game.apply(Action::Collect(1)).unwrap();
+        assert_eq!(
+            events[0],
+            Event::Collected {
+                player: 1,
+                group: 1,
+                pigs: 1
+            }
+        );
+        assert_eq!(game.pig_count(1), STARTING_PIGS + 1);
+        assert!(!game.board().is_occupied((1, 1).into()));
+        assert_eq!(game.current_player(), 0);
+    }
+
+    #[test]
+    fn pigs_in_the_pig_hole_are_gone() {
+        let mut game = GameState::new(1);
+        game.apply(Action::Roll(PIG_HOLE)).unwrap();
+        let events = game.apply(Action::Place((PIG_HOLE, 1).into())).unwrap();
+        assert_eq!(
+            events[0],
+            Event::PlacedInPigHole {
+                player: 0,
+                trough: (PIG_HOLE, 1).into()
+            }
+        );
+        assert!(!game.board().is_occupied((PIG_HOLE, 1).into()));
+        assert_eq!(game.pig_count(0), STARTING_PIGS - 1);
+
+        game.apply(Action::Roll(PIG_HOLE)).unwrap();
+        assert_eq!(game.phase(), Phase::PlacingInGroup(PIG_HOLE));
+    }
+
+    #[test]
+    fn placing_the_last_pig_wins() {
+        let mut game = play_mandatory_turns();
+        for _ in 0..game.pig_count(0) - 1 {
+            play_roll(&mut game, PIG_HOLE, (PIG_HOLE, 1));
+        }
+        game.apply(Action::Roll(PIG_HOLE)).unwrap();
+        let events = game.apply(Action::Place((PIG_HOLE, 1).into())).unwrap();
+        assert_eq!(events.last(), Some(&Event::Won { player: 0 }));
+        assert!(game.is_over());
+        assert_eq!(game.winner(), Some(0));
+        assert_eq!(game.apply(Action::Roll(1)), Err(RuleError::GameOver));
+    }
+
+    #[test]
+    fn invalid_placements_are_rejected_without_changing_the_state() {
+        let mut game = GameState::new(2);
+        play_roll(&mut game, 2, (2, 1));
+        game.apply(Action::Roll(2)).unwrap();
+        let before = game.clone();
+
+        assert_eq!(
+            game.apply(Action::Place((2, 1).into())),
+            Err(RuleError::TroughOccupied((2, 1).into()))
+        );
+        assert_eq!(
+            game.apply(Action::Place((3, 1).into())),
+            Err(RuleError::WrongGroup {
+                expected: 2,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            game.apply(Action::Place((2, 3).into())),
+            Err(RuleError::UnknownTrough((2, 3).into()))
+        );
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn rolls_outside_of_the_dice_are_rejected() {
+        let mut game = GameState::new(1);
+        assert_eq!(game.apply(Action::Roll(0)), Err(RuleError::InvalidRoll(0)));
+        assert_eq!(game.apply(Action::Roll(7)), Err(RuleError::InvalidRoll(7)));
+        assert_eq!(game.phase(), Phase::Rolling);
+    }
+
+    #[test]
+    fn rules_decide_pigs_and_opening_turns() {
+        let rules = RuleSet {
+            starting_pigs: 5,
+            opening_rolls: vec![3],
+            ..RuleSet::default()
+        };
+        let mut game = GameState::with_rules(1, rules);
+        assert_eq!(game.pig_count(0), 5);
+        assert_eq!(game.mandatory_rolls(), Some(3));
+        play_roll(&mut game, 5, (5, 1));
+        play_roll(&mut game, 5, (5, 2));
+        assert_eq!(game.turn_number(), 1);
+        play_roll(&mut game, 5, (5, 3));
+        assert_eq!(game.turn_number(), 2);
+        assert_eq!(game.mandatory_rolls(), None);
+    }
+
+    #[test]
+    fn turns_end_once_the_roll_limit_is_reached() {
+        let rules = RuleSet {
+            opening_rolls: Vec::new(),
+            max_rolls_per_turn: Some(2),
+            ..RuleSet::default()
+        };
+        let mut game = GameState::with_rules(2, rules);
+        play_roll(&mut game, 5, (5, 1));
+        assert_eq!(game.current_player(), 0);
+        assert!(game.can_end_turn());
+        play_roll(&mut game, 5, (5, 2));
+        assert_eq!(game.current_player(), 1);
+    }
+
+    #[test]
+    fn two_dice_roll_from_two_to_twelve() {
+        let rules = RuleSet {
+            dice: 2,
+            ..RuleSet::default()
+        };
+        let mut game = GameState::with_rules(1, rules);
+        assert_eq!(game.apply(Action::Roll(1)), Err(RuleError::InvalidRoll(1)));
+        game.apply(Action::Roll(12)).unwrap();
+        assert_eq!(game.phase(), Phase::PlacingInGroup(12));
+    }
+
+    /// Single player game that is past the turns with mandatory rolls, with an empty board.
+    fn play_mandatory_turns() -> GameState {
+        let mut game = GameState::new(1);
+        play_roll(&mut game, PIG_HOLE, (PIG_HOLE, 1));
+        play_roll(&mut game, PIG_HOLE, (PIG_HOLE, 1));
+        play_roll(&mut game, PIG_HOLE, (PIG_HOLE, 1));
+        assert_eq!(game.mandatory_rolls(), None);
+        game
+    }
+}
@@ -0,0 +1,218 @@
+//! Taking back moves in games where everybody shares one machine.
+
+use crate::game::{Action, Event, GameState, RuleError};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// How far back moves may be taken.
+/// Rolls can never be taken back, as that would allow rolling again until the result fits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UndoPolicy {
+    Off,
+    /// Only moves of the player on turn, since their last roll.
+    #[default]
+    CurrentTurn,
+    /// Everything since the last roll, including the end of the previous turn.
+    Unlimited,
+}
+
+impl UndoPolicy {
+    pub const ALL: [UndoPolicy; 3] = [
+        UndoPolicy::Off,
+        UndoPolicy::CurrentTurn,
+        UndoPolicy::Unlimited,
+    ];
+}
+
+impl Display for UndoPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UndoPolicy::Off => write!(f, "Off"),
+            UndoPolicy::CurrentTurn => write!(f, "Current turn"),
+            UndoPolicy::Unlimited => write!(f, "Unlimited"),
+        }
+    }
+}
+
+/// The moves of a game that may be taken back or made again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct History {
+    policy: UndoPolicy,
+    /// Every undoable action with the game before it, latest last.
+    done: Vec<(GameState, Action)>,
+    /// Actions that were taken back, latest last.
+    undone: Vec<Action>,
+}
+
+impl History {
+    pub fn new(policy: UndoPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn policy(&self) -> UndoPolicy {
+        self.policy
+    }
+
+    /// Applies the action to the game and remembers it, if the policy allows taking it back.
+    pub fn apply(&mut self, game: &mut GameState, action: Action) -> Result<Vec<Event>, RuleError> {
+        let events = self.record(game, action)?;
+        self.undone.clear();
+        Ok(events)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Resets the game to before the last undoable action. Returns the action, if there was one.
+    pub fn undo(&mut self, game: &mut GameState) -> Option<Action> {
+        let (previous, action) = self.done.pop()?;
+        *game = previous;
+        self.undone.push(action);
+        Some(action)
+    }
+
+    /// Applies the last action that was taken back again.
+    pub fn redo(&mut self, game: &mut GameState) -> Option<(Action, Vec<Event>)> {
+        let action = self.undone.pop()?;
+        match self.record(game, action) {
+            Ok(events) => Some((action, events)),
+            // The game was changed by something else, so the taken back actions no longer fit
+            Err(_) => {
+                self.undone.clear();
+                None
+            }
+        }
+    }
+
+    fn record(&mut self, game: &mut GameState, action: Action) -> Result<Vec<Event>, RuleError> {
+        let previous = game.clone();
+        let events = game.apply(action)?;
+        let ends_turn = events
+            .iter()
+            .any(|event| matches!(event, Event::TurnEnded { .. }));
+        let undoable = match (self.policy, action) {
+            (UndoPolicy::Off, _) => false,
+            (_, Action::Roll(_) | Action::Collect(_)) => false,
+            (UndoPolicy::CurrentTurn, _) => !ends_turn,
+            (UndoPolicy::Unlimited, _) => true,
+        };
+        if undoable && !game.is_over() {
+            self.done.push((previous, action));
+        } else {
+            self.done.clear();
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RuleSet;
+
+    /// Turns without mandatory rolls, so a single placement does not end the turn.
+    fn free_rolling_game() -> GameState {
+        let rules = RuleSet {
+            opening_rolls: Vec::new(),
+            ..RuleSet::default()
+        };
+        GameState::with_rules(2, rules)
+    }
+
+    fn play(history: &mut History, game: &mut GameState, actions: &[Action]) {
+        for action in actions {
+            history.apply(game, *action).unwrap();
+        }
+    }
+
+    #[test]
+    fn placements_are_taken_back_until_the_last_roll() {
+        let mut game = free_rolling_game();
+        let mut history = History::new(UndoPolicy::CurrentTurn);
+        play(&mut history, &mut game, &[Action::Roll(1)]);
+        let after_roll = game.clone();
+        history
+            .apply(&mut game, Action::Place((1, 1).into()))
+            .unwrap();
+        assert!(history.can_undo());
+        assert_eq!(history.undo(&mut game), Some(Action::Place((1, 1).into())));
+        assert_eq!(game, after_roll);
+        assert!(!history.can_undo());
+        assert_eq!(history.undo(&mut game), None);
+    }
+
+    #[test]
+    fn undone_actions_are_redone() {
+        let mut game = free_rolling_game();
+        let mut history = History::new(UndoPolicy::CurrentTurn);
+        play(
+            &mut history,
+            &mut game,
+            &[Action::Roll(1), Action::Place((1, 1).into())],
+        );
+        let expected = game.clone();
+        history.undo(&mut game);
+        assert!(history.can_redo());
+        let (action, _) = history.redo(&mut game).unwrap();
+        assert_eq!(action, Action::Place((1, 1).into()));
+        assert_eq!(game, expected);
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn new_actions_discard_the_undone_ones() {
+        let mut game = free_rolling_game();
+        let mut history = History::new(UndoPolicy::CurrentTurn);
+        play(
+            &mut history,
+            &mut game,
+            &[Action::Roll(2), Action::Place((2, 1).into())],
+        );
+        history.undo(&mut game);
+        history
+            .apply(&mut game, Action::Place((2, 2).into()))
+            .unwrap();
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn ending_the_turn_depends_on_the_policy() {
+        let actions = [
+            Action::Roll(1),
+            Action::Place((1, 1).into()),
+            Action::EndTurn,
+        ];
+        let mut game = free_rolling_game();
+        let mut history = History::new(UndoPolicy::CurrentTurn);
+        play(&mut history, &mut game, &actions);
+        assert!(!history.can_undo());
+
+        let mut game = free_rolling_game();
+        let mut history = History::new(UndoPolicy::Unlimited);
+        play(&mut history, &mut game, &actions);
+        assert_eq!(history.undo(&mut game), Some(Action::EndTurn));
+        assert_eq!(game.current_player(), 0);
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn nothing_is_taken_back_when_undo_is_off() {
+        let mut game = free_rolling_game();
+        let mut history = History::new(UndoPolicy::Off);
+        play(
+            &mut history,
+            &mut game,
+            &[Action::Roll(1), Action::Place((1, 1).into())],
+        );
+        assert!(!history.can_undo());
+    }
+}
@@ -0,0 +1,21 @@
+//! The rules of Pig Hole, free of any rendering or networking concerns.
+//!
+//! A match is represented by a [`GameState`] that only changes through [`GameState::apply`].
+//! Dice rolls are passed in as part of [`Action::Roll`], so the same sequence of actions
+//! always produces the same sequence of [`Event`]s, no matter where the rules are run.
+
+pub mod analysis;
+pub mod board;
+pub mod bot;
+pub mod dice;
+pub mod game;
+pub mod history;
+pub mod replay;
+pub mod rule_set;
+
+pub use board::{Board, Trough};
+pub use dice::FairDice;
+pub use game::{Action, Event, GameState, Phase, RuleError};
+pub use history::{History, UndoPolicy};
+pub use replay::{Replay, ReplayEvent};
+pub use rule_set::{RuleSet, RuleSetError};
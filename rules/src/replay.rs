@@ -0,0 +1,151 @@
+//! A record of everything that happened during a match, to watch it again later.
+
+use crate::game::{Action, GameState, RuleError};
+use crate::rule_set::RuleSet;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Replay {
+    pub rules: RuleSet,
+    /// Names of the players, in turn order.
+    pub players: Vec<String>,
+    /// Everything that happened, in order.
+    pub entries: Vec<ReplayEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    /// Milliseconds since the recording started.
+    pub time: u64,
+    pub event: ReplayEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReplayEvent {
+    Action(Action),
+    /// The whole game was replaced, e.g. when the match was continued or got out of sync.
+    Synced(GameState),
+    Joined(String),
+    Left(String),
+}
+
+/// An action of the replay that the rules reject, which means the recording is out of sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReplayError {
+    /// Index of the rejected entry.
+    pub entry: usize,
+    pub error: RuleError,
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Entry {} was rejected: {}", self.entry + 1, self.error)
+    }
+}
+
+impl Error for ReplayError {}
+
+impl Replay {
+    pub fn new(rules: RuleSet, players: Vec<String>) -> Self {
+        Self {
+            rules,
+            players,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Entries must be recorded in order of time.
+    pub fn record(&mut self, time: u64, event: ReplayEvent) {
+        debug_assert!(self.duration() <= time);
+        self.entries.push(ReplayEntry { time, event });
+    }
+
+    /// Time of the last entry.
+    pub fn duration(&self) -> u64 {
+        self.entries.last().map_or(0, |entry| entry.time)
+    }
+
+    /// How many entries happened up to the given time.
+    pub fn entries_until(&self, time: u64) -> usize {
+        self.entries.partition_point(|entry| entry.time <= time)
+    }
+
+    /// The game as it was after the first `count` entries.
+    pub fn game_after(&self, count: usize) -> Result<GameState, ReplayError> {
+        let mut game = GameState::with_rules(self.players.len(), self.rules.clone());
+        for (index, entry) in self.entries.iter().take(count).enumerate() {
+            match &entry.event {
+                ReplayEvent::Action(action) => {
+                    game.apply(*action).map_err(|error| ReplayError {
+                        entry: index,
+                        error,
+                    })?;
+                }
+                ReplayEvent::Synced(synced) => game = synced.clone(),
+                ReplayEvent::Joined(_) | ReplayEvent::Left(_) => (),
+            }
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replay(actions: &[Action]) -> Replay {
+        let mut replay = Replay::new(RuleSet::default(), vec!["A".into(), "B".into()]);
+        for (time, action) in actions.iter().enumerate() {
+            replay.record(time as u64 * 100, ReplayEvent::Action(*action));
+        }
+        replay
+    }
+
+    #[test]
+    fn the_game_is_rebuilt_from_the_actions() {
+        let replay = replay(&[Action::Roll(1), Action::Place((1, 1).into())]);
+        let mut expected = GameState::new(2);
+        assert_eq!(replay.game_after(0), Ok(expected.clone()));
+        expected.apply(Action::Roll(1)).unwrap();
+        assert_eq!(replay.game_after(1), Ok(expected.clone()));
+        expected.apply(Action::Place((1, 1).into())).unwrap();
+        assert_eq!(replay.game_after(2), Ok(expected.clone()));
+        assert_eq!(replay.game_after(10), Ok(expected));
+    }
+
+    #[test]
+    fn entries_are_found_by_time() {
+        let replay = replay(&[Action::Roll(1), Action::Place((1, 1).into())]);
+        assert_eq!(replay.duration(), 100);
+        assert_eq!(replay.entries_until(0), 1);
+        assert_eq!(replay.entries_until(99), 1);
+        assert_eq!(replay.entries_until(100), 2);
+    }
+
+    #[test]
+    fn synced_games_replace_the_game() {
+        let mut synced = GameState::new(2);
+        synced.apply(Action::Roll(4)).unwrap();
+        let mut replay = replay(&[Action::Roll(1)]);
+        replay.record(200, ReplayEvent::Synced(synced.clone()));
+        replay.record(300, ReplayEvent::Joined("C".into()));
+        assert_eq!(replay.game_after(3), Ok(synced));
+    }
+
+    #[test]
+    fn desyncs_point_at_the_rejected_entry() {
+        let replay = replay(&[Action::Roll(1), Action::Place((2, 1).into())]);
+        assert_eq!(
+            replay.game_after(2),
+            Err(ReplayError {
+                entry: 1,
+                error: RuleError::WrongGroup {
+                    expected: 1,
+                    actual: 2
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::board::Board;
+use crate::game::{DICE_FACES, STARTING_PIGS};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+
+/// The highest roll any rule set may allow, which keeps the board at a reasonable size.
+pub const MAX_ROLL: u8 = 24;
+
+/// The house rules a match is played with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub starting_pigs: u32,
+    /// The exact number of rolls in each of the first turns.
+    /// Every later turn needs at least one roll.
+    pub opening_rolls: Vec<usize>,
+    /// The most rolls allowed in a turn after the opening, if limited at all.
+    pub max_rolls_per_turn: Option<usize>,
+    /// How many dice are rolled at once. A roll is the sum of all of them.
+    pub dice: u8,
+    pub faces: u8,
+    /// Whether the highest group swallows pigs for good,
+    /// or is a single trough that can be filled and collected like any other.
+    pub bottomless_pig_hole: bool,
+}
+
+impl Default for RuleSet {
+    /// The classic rules.
+    fn default() -> Self {
+        Self {
+            starting_pigs: STARTING_PIGS,
+            opening_rolls: vec![1, 2],
+            max_rolls_per_turn: None,
+            dice: 1,
+            faces: DICE_FACES,
+            bottomless_pig_hole: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RuleSetError {
+    NoPigs,
+    NoDice,
+    TooFewFaces,
+    RollTooHigh,
+    EmptyOpeningTurn,
+    NoRollsAllowed,
+}
+
+impl Display for RuleSetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleSetError::NoPigs => write!(f, "Players need at least one pig"),
+            RuleSetError::NoDice => write!(f, "At least one die is needed"),
+            RuleSetError::TooFewFaces => write!(f, "Dice need at least two faces"),
+            RuleSetError::RollTooHigh => write!(f, "Rolls may not be higher than {}", MAX_ROLL),
+            RuleSetError::EmptyOpeningTurn => write!(f, "Every opening turn needs a roll"),
+            RuleSetError::NoRollsAllowed => write!(f, "Every turn needs at least one roll"),
+        }
+    }
+}
+
+impl Error for RuleSetError {}
+
+impl RuleSet {
+    pub fn validate(&self) -> Result<(), RuleSetError> {
+        if self.starting_pigs == 0 {
+            return Err(RuleSetError::NoPigs);
+        }
+        if self.dice == 0 {
+            return Err(RuleSetError::NoDice);
+        }
+        if self.faces < 2 {
+            return Err(RuleSetError::TooFewFaces);
+        }
+        if u16::from(self.dice) * u16::from(self.faces) > u16::from(MAX_ROLL) {
+            return Err(RuleSetError::RollTooHigh);
+        }
+        if self.opening_rolls.contains(&0) {
+            return Err(RuleSetError::EmptyOpeningTurn);
+        }
+        if self.max_rolls_per_turn == Some(0) {
+            return Err(RuleSetError::NoRollsAllowed);
+        }
+        Ok(())
+    }
+
+    pub fn lowest_roll(&self) -> u8 {
+        self.dice
+    }
+
+    /// Also the group of the pig hole.
+    pub fn highest_roll(&self) -> u8 {
+        self.dice * self.faces
+    }
+
+    /// Whether the board is the one of the original game, one die with six faces.
+    pub fn has_classic_board(&self) -> bool {
+        self.dice == 1 && self.faces == DICE_FACES
+    }
+
+    pub fn board(&self) -> Board {
+        Board::new(self)
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> u8 {
+        (0..self.dice).map(|_| rng.gen_range(1..=self.faces)).sum()
+    }
+
+    /// Every possible roll with its probability, starting with the lowest.
+    pub fn roll_probabilities(&self) -> Vec<(u8, f64)> {
+        // Ways to reach every sum, adding one die at a time
+        let mut ways = vec![1u64];
+        for _ in 0..self.dice {
+            let mut next = vec![0; ways.len() + usize::from(self.faces)];
+            for (sum, count) in ways.iter().enumerate() {
+                for face in 1..=usize::from(self.faces) {
+                    next[sum + face] += count;
+                }
+            }
+            ways = next;
+        }
+        let total: u64 = ways.iter().sum();
+        (self.lowest_roll()..=self.highest_roll())
+            .map(|roll| (roll, ways[usize::from(roll)] as f64 / total as f64))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn classic_rules_are_valid() {
+        assert_eq!(RuleSet::default().validate(), Ok(()));
+        assert!(RuleSet::default().has_classic_board());
+    }
+
+    #[test]
+    fn broken_rules_are_rejected() {
+        let rules = |change: fn(&mut RuleSet)| {
+            let mut rules = RuleSet::default();
+            change(&mut rules);
+            rules.validate()
+        };
+        assert_eq!(rules(|r| r.starting_pigs = 0), Err(RuleSetError::NoPigs));
+        assert_eq!(rules(|r| r.dice = 0), Err(RuleSetError::NoDice));
+        assert_eq!(rules(|r| r.faces = 1), Err(RuleSetError::TooFewFaces));
+        assert_eq!(rules(|r| r.dice = 5), Err(RuleSetError::RollTooHigh));
+        assert_eq!(
+            rules(|r| r.opening_rolls = vec![1, 0]),
+            Err(RuleSetError::EmptyOpeningTurn)
+        );
+        assert_eq!(
+            rules(|r| r.max_rolls_per_turn = Some(0)),
+            Err(RuleSetError::NoRollsAllowed)
+        );
+    }
+
+    #[test]
+    fn two_dice_favour_the_middle() {
+        let rules = RuleSet {
+            dice: 2,
+            ..RuleSet::default()
+        };
+        let probabilities = rules.roll_probabilities();
+        assert_eq!(probabilities.len(), 11);
+        assert_eq!(probabilities[0], (2, 1.0 / 36.0));
+        assert_eq!(probabilities[5], (7, 6.0 / 36.0));
+        assert!((probabilities.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rolls_stay_within_bounds() {
+        let rules = RuleSet {
+            dice: 3,
+            faces: 4,
+            ..RuleSet::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let roll = rules.roll(&mut rng);
+            assert!((3..=12).contains(&roll));
+        }
+    }
+}
@@ -32,6 +32,7 @@ fn main() {
         .add_startup_system(init)
         // Receive Server Events
         .add_system_to_stage(Stage::ReceiveEvents, events::authorization_event)
+        .add_system_to_stage(Stage::ReceiveEvents, events::finish_authorizations)
         .add_system_to_stage(Stage::ReceiveEvents, events::connection_event)
         .add_system_to_stage(Stage::ReceiveEvents, events::disconnection_event)
         .add_system_to_stage(Stage::ReceiveEvents, events::receive_message_event)
@@ -1,8 +1,13 @@
 use bevy::prelude::*;
+use bevy::tasks::Task;
 use naia_bevy_server::{RoomKey, UserKey};
 use std::collections::HashMap;
 
 pub struct Global {
     pub main_room_key: RoomKey,
     pub user_to_prediction_map: HashMap<UserKey, Entity>,
+    /// Where the session tokens of connecting users are checked.
+    pub matchmaker_url: String,
+    /// Users waiting for the matchmaker to confirm their session.
+    pub pending_authorizations: Vec<(UserKey, Task<bool>)>,
 }
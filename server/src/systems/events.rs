@@ -1,5 +1,8 @@
 use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
+use futures_lite::future;
 
+use matchmaker_models::client_api::User;
 use naia_bevy_server::{
     events::{AuthorizationEvent, ConnectionEvent, DisconnectionEvent, MessageEvent},
     Server,
@@ -12,20 +15,64 @@ use shared::{
 
 use crate::resources::Global;
 
+/// Users log in with their session token as password, which is checked at the matchmaker.
 pub fn authorization_event(
     mut event_reader: EventReader<AuthorizationEvent<Protocol>>,
-    mut server: Server<Protocol, Channels>,
+    mut global: ResMut<Global>,
+    task_pool: Res<IoTaskPool>,
 ) {
     for event in event_reader.iter() {
         if let AuthorizationEvent(user_key, Protocol::Auth(auth)) = event {
-            if *auth.username == "charlie" && *auth.password == "12345" {
-                // Accept incoming connection
-                server.accept_connection(user_key);
-            } else {
-                // Reject incoming connection
-                server.reject_connection(user_key);
-            }
+            let url = format!(
+                "{}/sessions/current",
+                global.matchmaker_url.trim_end_matches('/')
+            );
+            let token = (*auth.password).clone();
+            let user_id = *auth.user_id;
+            let username = (*auth.username).clone();
+            let task =
+                task_pool.spawn(async move { is_session_of(&url, &token, user_id, &username) });
+            global.pending_authorizations.push((*user_key, task));
+        }
+    }
+}
+
+/// Accepts or rejects the users whose session the matchmaker has answered for.
+pub fn finish_authorizations(mut global: ResMut<Global>, mut server: Server<Protocol, Channels>) {
+    global
+        .pending_authorizations
+        .retain_mut(
+            |(user_key, task)| match future::block_on(future::poll_once(task)) {
+                Some(true) => {
+                    server.accept_connection(user_key);
+                    false
+                }
+                Some(false) => {
+                    server.reject_connection(user_key);
+                    false
+                }
+                None => true,
+            },
+        );
+}
+
+/// The token is sent as a header, so it does not end up in the logs of the matchmaker.
+fn is_session_of(url: &str, token: &str, user_id: u64, username: &str) -> bool {
+    let response = match ureq::get(url)
+        .set("Authorization", &format!("Bearer {}", token))
+        .call()
+    {
+        Ok(response) => response,
+        // The session does not exist or has expired
+        Err(ureq::Error::Status(401, _)) => return false,
+        Err(error) => {
+            warn!("Could not check a session at the matchmaker: {}", error);
+            return false;
         }
+    };
+    match response.into_json::<User>() {
+        Ok(user) => user.id == user_id && user.username == username,
+        Err(_) => false,
     }
 }
 
@@ -9,6 +9,8 @@ use shared::{channels::Channels, protocol::Protocol};
 
 use crate::resources::Global;
 
+const DEFAULT_MATCHMAKER_URL: &str = "http://127.0.0.1:8000";
+
 pub fn init(mut commands: Commands, mut server: Server<Protocol, Channels>) {
     log::info!("Naia Bevy Server Demo is running");
 
@@ -35,5 +37,8 @@ pub fn init(mut commands: Commands, mut server: Server<Protocol, Channels>) {
     commands.insert_resource(Global {
         main_room_key,
         user_to_prediction_map: HashMap::new(),
+        matchmaker_url: std::env::var("PIG_HOLE_MATCHMAKER_URL")
+            .unwrap_or_else(|_| DEFAULT_MATCHMAKER_URL.to_string()),
+        pending_authorizations: Vec::new(),
     })
 }
@@ -2,15 +2,18 @@ use bevy::prelude::*;
 
 use naia_shared::{Property, Replicate};
 
+/// The password is the session token the matchmaker handed out when the user logged in.
 #[derive(Component, Replicate)]
 #[protocol_path = "crate::protocol::Protocol"]
 pub struct Auth {
+    /// Id of the account the session belongs to.
+    pub user_id: Property<u64>,
     pub username: Property<String>,
     pub password: Property<String>,
 }
 
 impl Auth {
-    pub fn new(username: &str, password: &str) -> Self {
-        Auth::new_complete(username.to_string(), password.to_string())
+    pub fn new(user_id: u64, username: &str, password: &str) -> Self {
+        Auth::new_complete(user_id, username.to_string(), password.to_string())
     }
 }
@@ -0,0 +1,196 @@
+//! Plays many games of Pig Hole between bots, without a window, and prints statistics about them.
+//! Helps deciding on house rules before trying them with humans.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rules::bot::Difficulty;
+use rules::RuleSet;
+use std::fs;
+
+mod statistics;
+
+const USAGE: &str = "Usage: simulator [--games <count>] [--bots <easy|medium|hard>,...] \
+                     [--seed <number>] [--format <csv|json>] [--output <file>] \
+                     [--starting-pigs <count>] [--opening-rolls <count>,...|none] \
+                     [--max-rolls <count>] [--dice <count>] [--faces <count>] \
+                     [--fillable-pig-hole]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    games: usize,
+    bots: Vec<Difficulty>,
+    rules: RuleSet,
+    seed: Option<u64>,
+    format: Format,
+    output: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            games: 1000,
+            bots: Difficulty::ALL.to_vec(),
+            rules: RuleSet::default(),
+            seed: None,
+            format: Format::Csv,
+            output: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+fn main() {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let statistics = statistics::simulate(&options.bots, &options.rules, options.games, &mut rng);
+    let report = match options.format {
+        Format::Csv => statistics.to_csv(),
+        Format::Json => serde_json::to_string_pretty(&statistics).unwrap(),
+    };
+    match &options.output {
+        Some(path) => {
+            if let Err(error) = fs::write(path, report) {
+                eprintln!("Failed to write {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+        None => println!("{}", report),
+    }
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--games" => options.games = parse_number(&value()?)?,
+            "--bots" => {
+                options.bots = value()?
+                    .split(',')
+                    .map(parse_difficulty)
+                    .collect::<Result<_, _>>()?
+            }
+            "--seed" => options.seed = Some(parse_number(&value()?)?),
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    format => return Err(format!("Unknown format {}", format)),
+                }
+            }
+            "--output" => options.output = Some(value()?),
+            "--starting-pigs" => options.rules.starting_pigs = parse_number(&value()?)?,
+            "--opening-rolls" => {
+                options.rules.opening_rolls = match value()?.as_str() {
+                    "none" => Vec::new(),
+                    rolls => rolls
+                        .split(',')
+                        .map(parse_number)
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            "--max-rolls" => options.rules.max_rolls_per_turn = Some(parse_number(&value()?)?),
+            "--dice" => options.rules.dice = parse_number(&value()?)?,
+            "--faces" => options.rules.faces = parse_number(&value()?)?,
+            "--fillable-pig-hole" => options.rules.bottomless_pig_hole = false,
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    if options.bots.is_empty() {
+        return Err("At least one bot is needed".to_string());
+    }
+    options
+        .rules
+        .validate()
+        .map_err(|error| error.to_string())?;
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid number", value))
+}
+
+fn parse_difficulty(name: &str) -> Result<Difficulty, String> {
+    Difficulty::ALL
+        .into_iter()
+        .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(name.trim()))
+        .ok_or(format!("Unknown bot {}", name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        parse_options(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn defaults_pit_every_difficulty_against_each_other() {
+        assert_eq!(parse(""), Ok(Options::default()));
+        assert_eq!(Options::default().bots, Difficulty::ALL.to_vec());
+    }
+
+    #[test]
+    fn bots_are_chosen_by_difficulty_name() {
+        let options = parse("--games 10 --bots hard,Easy,hard --format json").unwrap();
+        assert_eq!(options.games, 10);
+        assert_eq!(
+            options.bots,
+            vec![
+                Difficulty::Expectation,
+                Difficulty::Random,
+                Difficulty::Expectation
+            ]
+        );
+        assert_eq!(options.format, Format::Json);
+    }
+
+    #[test]
+    fn house_rules_can_be_simulated() {
+        let options =
+            parse("--starting-pigs 12 --opening-rolls none --max-rolls 3 --dice 2 --faces 4 --fillable-pig-hole")
+                .unwrap();
+        assert_eq!(
+            options.rules,
+            RuleSet {
+                starting_pigs: 12,
+                opening_rolls: Vec::new(),
+                max_rolls_per_turn: Some(3),
+                dice: 2,
+                faces: 4,
+                bottomless_pig_hole: false,
+            }
+        );
+        assert_eq!(
+            parse("--opening-rolls 1,2,3").unwrap().rules.opening_rolls,
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert!(parse("--games many").is_err());
+        assert!(parse("--bots impossible").is_err());
+        assert!(parse("--seed").is_err());
+        assert!(parse("--fast").is_err());
+        assert!(parse("--dice 0").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use rand::Rng;
+use rules::bot::{self, Difficulty};
+use rules::{Event, GameState, RuleSet};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The outcome of a single simulated game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameReport {
+    pub winner: usize,
+    pub turns: usize,
+    pub actions: usize,
+    /// How often each group was collected.
+    pub collections: BTreeMap<u8, u32>,
+}
+
+/// Plays a full game with one bot per seat, in turn order.
+/// Returns `None` if the game did not end.
+pub fn play_game(
+    strategies: &[Difficulty],
+    rules: &RuleSet,
+    rng: &mut impl Rng,
+) -> Option<GameReport> {
+    let mut game = GameState::with_rules(strategies.len(), rules.clone());
+    let mut collections: BTreeMap<u8, u32> =
+        game.board().groups().map(|group| (group, 0)).collect();
+    let mut actions = 0;
+    while actions < bot::MAX_ACTIONS {
+        let difficulty = strategies[game.current_player()];
+        let action = match bot::next_action(&game, difficulty, rng) {
+            Some(action) => action,
+            None => break,
+        };
+        for event in game.apply(action).ok()? {
+            if let Event::Collected { group, .. } = event {
+                *collections.entry(group).or_default() += 1;
+            }
+        }
+        actions += 1;
+    }
+    Some(GameReport {
+        winner: game.winner()?,
+        turns: game.turn_number(),
+        actions,
+        collections,
+    })
+}
+
+/// Aggregated results of many simulated games.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Statistics {
+    pub games: usize,
+    pub unfinished_games: usize,
+    pub average_turns: f64,
+    pub average_actions: f64,
+    /// Share of finished games won by whoever went first.
+    pub first_player_win_rate: f64,
+    /// Share of finished games won by each strategy.
+    pub win_rates: BTreeMap<String, f64>,
+    /// How often each group was collected per game.
+    pub average_collections: BTreeMap<u8, f64>,
+}
+
+/// Plays `games` games between the given strategies.
+/// The seats are rotated after every game, so no strategy always goes first.
+pub fn simulate(
+    strategies: &[Difficulty],
+    rules: &RuleSet,
+    games: usize,
+    rng: &mut impl Rng,
+) -> Statistics {
+    let mut reports = Vec::with_capacity(games);
+    let mut winners = Vec::with_capacity(games);
+    for game in 0..games {
+        let mut seats = strategies.to_vec();
+        seats.rotate_left(game % strategies.len());
+        if let Some(report) = play_game(&seats, rules, rng) {
+            winners.push(seats[report.winner]);
+            reports.push(report);
+        }
+    }
+
+    let finished = reports.len().max(1) as f64;
+    let average =
+        |value: &dyn Fn(&GameReport) -> f64| reports.iter().map(value).sum::<f64>() / finished;
+    let mut wins: BTreeMap<String, usize> = strategies
+        .iter()
+        .map(|strategy| (strategy.to_string(), 0))
+        .collect();
+    for winner in winners {
+        *wins.entry(winner.to_string()).or_default() += 1;
+    }
+    Statistics {
+        games,
+        unfinished_games: games - reports.len(),
+        average_turns: average(&|report| report.turns as f64),
+        average_actions: average(&|report| report.actions as f64),
+        first_player_win_rate: average(&|report| f64::from(u8::from(report.winner == 0))),
+        win_rates: wins
+            .into_iter()
+            .map(|(strategy, count)| (strategy, count as f64 / finished))
+            .collect(),
+        average_collections: rules
+            .board()
+            .groups()
+            .map(|group| {
+                let collections = average(&|report| f64::from(report.collections[&group]));
+                (group, collections)
+            })
+            .collect(),
+    }
+}
+
+impl Statistics {
+    /// One `metric,value` row per number.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("metric,value\n");
+        let mut row = |metric: &str, value: f64| writeln!(csv, "{},{}", metric, value).unwrap();
+        row("games", self.games as f64);
+        row("unfinished_games", self.unfinished_games as f64);
+        row("average_turns", self.average_turns);
+        row("average_actions", self.average_actions);
+        row("first_player_win_rate", self.first_player_win_rate);
+        for (strategy, win_rate) in &self.win_rates {
+            row(&format!("win_rate_{}", strategy.to_lowercase()), *win_rate);
+        }
+        for (group, collections) in &self.average_collections {
+            row(
+                &format!("average_collections_group_{}", group),
+                *collections,
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn every_game_has_a_winner() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let strategies = [Difficulty::Random, Difficulty::Expectation];
+        let report = play_game(&strategies, &RuleSet::default(), &mut rng).unwrap();
+        assert!(report.winner < strategies.len());
+        assert!(report.turns > 2);
+        assert_eq!(report.collections.len(), 6);
+        assert_eq!(report.collections[&6], 0);
+    }
+
+    #[test]
+    fn win_rates_add_up_to_one() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let strategies = [
+            Difficulty::Random,
+            Difficulty::Cautious,
+            Difficulty::Expectation,
+        ];
+        let statistics = simulate(&strategies, &RuleSet::default(), 30, &mut rng);
+        assert_eq!(statistics.games, 30);
+        assert_eq!(statistics.unfinished_games, 0);
+        assert_eq!(statistics.win_rates.len(), 3);
+        assert!((statistics.win_rates.values().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_number() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let strategies = [Difficulty::Cautious, Difficulty::Cautious];
+        let statistics = simulate(&strategies, &RuleSet::default(), 4, &mut rng);
+        let csv = statistics.to_csv();
+        assert!(csv.starts_with("metric,value\ngames,4\n"));
+        assert!(csv.contains("win_rate_medium,1\n"));
+        assert_eq!(csv.lines().count(), 1 + 5 + 1 + 6);
+    }
+
+    #[test]
+    fn variants_change_the_board() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let rules = RuleSet {
+            starting_pigs: 8,
+            dice: 2,
+            faces: 4,
+            bottomless_pig_hole: false,
+            ..RuleSet::default()
+        };
+        let statistics = simulate(&[Difficulty::Expectation; 2], &rules, 10, &mut rng);
+        assert_eq!(statistics.unfinished_games, 0);
+        let groups: Vec<_> = statistics.average_collections.keys().copied().collect();
+        assert_eq!(groups, (2..=8).collect::<Vec<_>>());
+    }
+}